@@ -1,23 +1,57 @@
-use crate::table::Table;
+use crate::parser::{
+    alter::{AlterOperation, AlterTableQuery},
+    create::{CreateQuery, ForeignKeyAction, ParsedColumn},
+    delete::DeleteQuery,
+    insert::InsertValue,
+    select::{Projection, SelectQuery},
+    sequence::CreateSequenceQuery,
+    update::UpdateQuery,
+};
+use crate::table::{join::JoinedTables, DataType, ForeignKey, QueryResult, Table};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct Database {
     pub tables: Vec<Table>,
+    pub sequences: Vec<Sequence>,
+}
+
+/// A counter created by CREATE SEQUENCE. `next` is what the next `nextval` gives, a value is
+/// never handed out twice.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Sequence {
+    pub name: String,
+    pub next: i64,
+    pub increment: i64,
+}
+
+/// What a DELETE does once the foreign keys referencing the deleted rows are followed: the
+/// rows to delete per table and the (table, column, rows) to set to NULL.
+#[derive(Default)]
+struct DeletePlan {
+    deleted: HashMap<String, BTreeSet<usize>>,
+    nulled: Vec<(String, String, Vec<usize>)>,
 }
 
 impl Database {
     pub fn new() -> Database {
-        return Database { tables: vec![] };
+        Database {
+            tables: vec![],
+            sequences: vec![],
+        }
     }
 
+    /// Table names are case-insensitive, like in MySQL. Column names are still matched exactly.
     pub fn table_exists(&self, tname: String) -> bool {
-        self.tables.iter().any(|t| t.name == tname)
+        self.tables
+            .iter()
+            .any(|t| t.name.eq_ignore_ascii_case(&tname))
     }
 
     pub fn get_table(&self, tname: String) -> &Table {
         for t in &self.tables {
-            if t.name == tname {
+            if t.name.eq_ignore_ascii_case(&tname) {
                 return t;
             }
         }
@@ -26,10 +60,1098 @@ impl Database {
 
     pub fn get_table_mut(&mut self, tname: String) -> &mut Table {
         for t in &mut self.tables {
-            if t.name == tname {
+            if t.name.eq_ignore_ascii_case(&tname) {
                 return t;
             }
         }
         panic!("Cannot find table, {}", tname);
     }
+
+    /// The table holding the index called `name`.
+    pub fn get_index_table_mut(&mut self, name: &str) -> Option<&mut Table> {
+        self.tables.iter_mut().find(|t| t.has_index(name))
+    }
+
+    /// Creates the table `cq` describes, unless a table with the same name exists. A foreign
+    /// key has to reference the primary key or a unique column of the same type, in a table
+    /// that exists or in the new table itself. The table of a CREATE TABLE ... AS SELECT
+    /// takes the columns of the query, typed like them, and its rows.
+    pub fn create_table(&mut self, mut cq: CreateQuery) -> Result<(), String> {
+        if self.table_exists(cq.table_name.to_string()) {
+            return match cq.if_not_exists {
+                true => Ok(()),
+                false => Err(format!("Table {} already exists", cq.table_name)),
+            };
+        }
+        let rows = match cq.query.take() {
+            Some(sq) => {
+                let result = self.query(sq)?;
+                cq.columns = result_columns(&result)?;
+                result.rows()
+            }
+            None => vec![],
+        };
+        for fk in &mut cq.foreign_keys {
+            // Foreign keys spell the name of their parent the way it was created.
+            if fk.parent_table.eq_ignore_ascii_case(&cq.table_name) {
+                fk.parent_table = cq.table_name.to_string();
+            } else if self.table_exists(fk.parent_table.to_string()) {
+                fk.parent_table = self.get_table(fk.parent_table.to_string()).name.to_string();
+            }
+            if fk.parent_column.is_none() && fk.parent_table != cq.table_name {
+                if !self.table_exists(fk.parent_table.to_string()) {
+                    return Err(format!(
+                        "foreign key {} references unknown table {}",
+                        fk.name, fk.parent_table
+                    ));
+                }
+                let parent = self.get_table(fk.parent_table.to_string());
+                fk.parent_column = parent
+                    .columns
+                    .iter()
+                    .find(|c| c.is_primary_key)
+                    .map(|c| c.name.to_string());
+            } else if fk.parent_column.is_none() {
+                fk.parent_column = cq
+                    .columns
+                    .iter()
+                    .find(|c| c.is_pk)
+                    .map(|c| c.name.to_string());
+            }
+        }
+
+        let mut table = Table::new(cq)?;
+        if !rows.is_empty() {
+            let cols = table
+                .columns
+                .iter()
+                .map(|c| c.name.to_string())
+                .collect::<Vec<String>>();
+            table.insert_row(&cols, &rows)?;
+        }
+        for fk in &table.foreign_keys {
+            let parent = match fk.parent_table == table.name {
+                true => &table,
+                false if self.table_exists(fk.parent_table.to_string()) => {
+                    self.get_table(fk.parent_table.to_string())
+                }
+                false => {
+                    return Err(format!(
+                        "foreign key {} references unknown table {}",
+                        fk.name, fk.parent_table
+                    ))
+                }
+            };
+            if !parent.column_exist(fk.parent_column.to_string()) {
+                return Err(format!(
+                    "foreign key {} references unknown column {}.{}",
+                    fk.name, fk.parent_table, fk.parent_column
+                ));
+            }
+            let parent_column = parent.get_column(fk.parent_column.to_string());
+            if !parent_column.is_unique {
+                return Err(format!(
+                    "foreign key {} must reference a primary key or unique column, {}.{} is neither",
+                    fk.name, fk.parent_table, fk.parent_column
+                ));
+            }
+            let column = table.get_column(fk.column.to_string());
+            if column.datatype != parent_column.datatype {
+                return Err(format!(
+                    "foreign key {}: column {} of type {} cannot reference {}.{} of type {}",
+                    fk.name,
+                    fk.column,
+                    column.datatype,
+                    fk.parent_table,
+                    fk.parent_column,
+                    parent_column.datatype
+                ));
+            }
+        }
+        self.tables.push(table);
+        Ok(())
+    }
+
+    /// Runs the select query `sq`, with or without joins, and returns its typed result.
+    pub fn query(&self, mut sq: SelectQuery) -> Result<QueryResult, String> {
+        for name in std::iter::once(&sq.from).chain(sq.joins.iter().map(|j| &j.table)) {
+            if !self.table_exists(name.to_string()) {
+                return Err(format!("table {} doesn't exist", name));
+            }
+        }
+        let table = self.get_table(sq.from.to_string());
+        if !sq.joins.is_empty() {
+            let mut tables = vec![table];
+            for join in &sq.joins {
+                tables.push(self.get_table(join.table.to_string()));
+            }
+            return JoinedTables::new(tables, &sq)?.select_result(&sq);
+        }
+
+        if sq.projection.contains(&Projection::Column("*".to_string())) {
+            let new_projections = table
+                .columns
+                .iter()
+                .map(|c| Projection::Column(c.name.to_string()))
+                .collect::<Vec<Projection>>();
+            sq.insert_projections(new_projections);
+        }
+        if let Some(col) = sq
+            .projection
+            .iter()
+            .filter_map(|p| p.column())
+            .find(|col| !table.column_exist(col.to_string()))
+        {
+            return Err(format!(
+                "cannot find column {} in table {}",
+                col, table.name
+            ));
+        }
+        table.select_result(&sq)
+    }
+
+    pub fn sequence_exists(&self, name: &str) -> bool {
+        self.sequences.iter().any(|s| s.name == name)
+    }
+
+    /// Creates the sequence `csq` describes.
+    pub fn create_sequence(&mut self, csq: &CreateSequenceQuery) -> Result<(), String> {
+        if self.sequence_exists(&csq.name) {
+            return Err(format!("Sequence {} already exists", csq.name));
+        }
+        self.sequences.push(Sequence {
+            name: csq.name.to_string(),
+            next: csq.start,
+            increment: csq.increment,
+        });
+        Ok(())
+    }
+
+    /// Drops the sequence `name`, returning false when there is none.
+    pub fn drop_sequence(&mut self, name: &str) -> bool {
+        let count = self.sequences.len();
+        self.sequences.retain(|s| s.name != name);
+        self.sequences.len() != count
+    }
+
+    /// Replaces each `nextval` in `values` with the next value of its sequence.
+    pub fn resolve_values(
+        &mut self,
+        values: &[Vec<InsertValue>],
+    ) -> Result<Vec<Vec<Option<String>>>, String> {
+        let mut resolved = vec![];
+        for row in values {
+            let mut resolved_row = vec![];
+            for value in row {
+                resolved_row.push(match value {
+                    InsertValue::Value(val) => val.clone(),
+                    InsertValue::NextVal(name) => Some(self.next_value(name)?.to_string()),
+                });
+            }
+            resolved.push(resolved_row);
+        }
+        Ok(resolved)
+    }
+
+    /// The rows `sq` returns for an INSERT ... SELECT into `columns`, which the query has to
+    /// match in number.
+    pub fn select_values(
+        &self,
+        sq: SelectQuery,
+        columns: &[String],
+    ) -> Result<Vec<Vec<Option<String>>>, String> {
+        let result = self.query(sq)?;
+        if result.columns.len() != columns.len() {
+            return Err(format!(
+                "expected {} columns, the query returns {}",
+                columns.len(),
+                result.columns.len()
+            ));
+        }
+        Ok(result.rows())
+    }
+
+    fn next_value(&mut self, name: &str) -> Result<i64, String> {
+        let sequence = self
+            .sequences
+            .iter_mut()
+            .find(|s| s.name == name)
+            .ok_or_else(|| format!("Sequence {} doesn't exist", name))?;
+        let val = sequence.next;
+        sequence.next = val
+            .checked_add(sequence.increment)
+            .ok_or_else(|| format!("Sequence {} reached its limit", name))?;
+        Ok(val)
+    }
+
+    /// Applies the operations of an ALTER TABLE in order, none of them when one fails. Foreign
+    /// keys follow renamed tables and columns, a column they use keeps its type.
+    pub fn alter_table(&mut self, atq: &AlterTableQuery) -> Result<(), String> {
+        let table_name = self.get_table(atq.table_name.to_string()).name.to_string();
+        self.all_or_nothing(&table_name, |db| {
+            let mut table_name = table_name.to_string();
+            for operation in &atq.operations {
+                match operation {
+                    AlterOperation::AddColumn(column) => {
+                        db.get_table_mut(table_name.to_string())
+                            .add_column(column)?;
+                    }
+                    AlterOperation::DropColumn { name, if_exists } => {
+                        let table = db.get_table(table_name.to_string());
+                        if *if_exists && !table.column_exist(name.to_string()) {
+                            continue;
+                        }
+                        db.check_unreferenced(&table_name, name, "drop")?;
+                        db.get_table_mut(table_name.to_string()).drop_column(name)?;
+                    }
+                    AlterOperation::RenameColumn { old_name, new_name } => {
+                        db.get_table_mut(table_name.to_string())
+                            .rename_column(old_name, new_name)?;
+                        for fk in db.tables.iter_mut().flat_map(|t| &mut t.foreign_keys) {
+                            if fk.parent_table == table_name && fk.parent_column == *old_name {
+                                fk.parent_column = new_name.to_string();
+                            }
+                        }
+                    }
+                    AlterOperation::RenameTable(new_name) => {
+                        if !new_name.eq_ignore_ascii_case(&table_name)
+                            && db.table_exists(new_name.to_string())
+                        {
+                            return Err(format!("Table {} already exists", new_name));
+                        }
+                        db.get_table_mut(table_name.to_string()).name = new_name.to_string();
+                        for fk in db.tables.iter_mut().flat_map(|t| &mut t.foreign_keys) {
+                            if fk.parent_table == table_name {
+                                fk.parent_table = new_name.to_string();
+                            }
+                        }
+                        table_name = new_name.to_string();
+                    }
+                    AlterOperation::ChangeType { column, datatype } => {
+                        let table = db.get_table(table_name.to_string());
+                        if table.column_exist(column.to_string())
+                            && table.get_column(column.to_string()).datatype
+                                == DataType::new(datatype.to_string())
+                        {
+                            continue;
+                        }
+                        db.check_unreferenced(&table_name, column, "change the type of")?;
+                        db.get_table_mut(table_name.to_string())
+                            .change_column_type(column, datatype)?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Drops the tables `names`. A table that another table references through a foreign key
+    /// can only go together with that table.
+    pub fn drop_tables(&mut self, names: &[String], if_exists: bool) -> Result<(), String> {
+        if !if_exists {
+            if let Some(name) = names.iter().find(|n| !self.table_exists(n.to_string())) {
+                return Err(format!("Unknown table {}", name));
+            }
+        }
+        for name in names {
+            if let Some((child, fk)) = self
+                .references_to(name)
+                .find(|(child, _)| !names.iter().any(|n| n.eq_ignore_ascii_case(&child.name)))
+            {
+                return Err(format!(
+                    "Cannot drop table {}, table {} references it through foreign key {}",
+                    name, child.name, fk.name
+                ));
+            }
+        }
+        self.tables
+            .retain(|t| !names.iter().any(|n| n.eq_ignore_ascii_case(&t.name)));
+        Ok(())
+    }
+
+    /// Deletes every row of the table `table_name`, which no other table may reference, and
+    /// resets its AUTO_INCREMENT counter like MySQL does.
+    pub fn truncate_table(&mut self, table_name: &str) -> Result<usize, String> {
+        if let Some((child, fk)) = self
+            .references_to(table_name)
+            .find(|(child, _)| !child.name.eq_ignore_ascii_case(table_name))
+        {
+            return Err(format!(
+                "Cannot truncate table {}, table {} references it through foreign key {}",
+                table_name, child.name, fk.name
+            ));
+        }
+        Ok(self.get_table_mut(table_name.to_string()).truncate())
+    }
+
+    /// Inserts `values` into the table `table_name` once every foreign key value they hold is
+    /// found in its parent table.
+    pub fn insert_rows(
+        &mut self,
+        table_name: &str,
+        cols: &[String],
+        values: &[Vec<Option<String>>],
+    ) -> Result<(), String> {
+        let table = self.get_table(table_name.to_string());
+        for fk in &table.foreign_keys {
+            let default = &table.get_column(fk.column.to_string()).default;
+            let pos = cols.iter().position(|c| *c == fk.column);
+            for value in values {
+                let val = match pos {
+                    Some(pos) => &value[pos],
+                    None => default,
+                };
+                if let Some(val) = val {
+                    self.check_reference(fk, val)?;
+                }
+            }
+        }
+        self.get_table_mut(table_name.to_string())
+            .insert_row(cols, values)
+    }
+
+    /// Applies an UPDATE, following the foreign keys referencing the columns it changes.
+    pub fn update_rows(&mut self, uq: &UpdateQuery) -> Result<usize, String> {
+        let table = self.get_table(uq.table_name.to_string());
+        if table.foreign_keys.is_empty() && self.references_to(&table.name).next().is_none() {
+            return self
+                .get_table_mut(uq.table_name.to_string())
+                .update_rows(uq);
+        }
+        let (rows, new_values) = table.evaluate_update(uq)?;
+        let table_name = table.name.to_string();
+        self.all_or_nothing(&table_name, |db| {
+            db.write_values(&table_name, &rows, &uq.columns(), &new_values)
+        })?;
+        Ok(rows.len())
+    }
+
+    /// Gives `rows` of the table `table_name` the values in `new_values`. A changed key that
+    /// rows of other tables reference is refused, carried over to them or set to NULL there,
+    /// depending on the ON UPDATE action of their foreign key.
+    fn write_values(
+        &mut self,
+        table_name: &str,
+        rows: &[usize],
+        columns: &[String],
+        new_values: &[Vec<Option<String>>],
+    ) -> Result<(), String> {
+        let table = self.get_table(table_name.to_string());
+        for fk in &table.foreign_keys {
+            if let Some(assigned) = columns.iter().position(|c| *c == fk.column) {
+                for val in new_values[assigned].iter().flatten() {
+                    self.check_reference(fk, val)?;
+                }
+            }
+        }
+
+        let mut cascades = vec![];
+        for (child, fk) in self.references_to(table_name) {
+            let assigned = match columns.iter().position(|c| *c == fk.parent_column) {
+                Some(assigned) => assigned,
+                None => continue,
+            };
+            let mut changes = HashMap::new();
+            for (row, new_key) in rows.iter().zip(&new_values[assigned]) {
+                match table.get_value(&fk.parent_column, *row) {
+                    Some(old_key) if Some(&old_key) != new_key.as_ref() => {
+                        changes.insert(old_key, new_key.clone());
+                    }
+                    _ => {}
+                }
+            }
+            let keys = changes.keys().cloned().collect::<HashSet<String>>();
+            let child_rows = child.rows_holding(&fk.column, &keys);
+            if child_rows.is_empty() {
+                continue;
+            }
+            let child_values = match fk.on_update {
+                ForeignKeyAction::Restrict => {
+                    return Err(restricted("update", table_name, &child.name, &fk.name))
+                }
+                ForeignKeyAction::Cascade => child_rows
+                    .iter()
+                    .map(|row| changes[&child.get_value(&fk.column, *row).unwrap()].clone())
+                    .collect(),
+                ForeignKeyAction::SetNull => vec![None; child_rows.len()],
+            };
+            cascades.push((
+                child.name.to_string(),
+                fk.column.to_string(),
+                child_rows,
+                child_values,
+            ));
+        }
+
+        self.get_table_mut(table_name.to_string())
+            .write_values(rows, columns, new_values)?;
+        for (child, column, child_rows, child_values) in cascades {
+            self.write_values(&child, &child_rows, &[column], &[child_values])?;
+        }
+        Ok(())
+    }
+
+    /// Applies a DELETE, following the foreign keys referencing the deleted rows. Nothing is
+    /// deleted when one of them restricts it or a row cannot take the NULL it is given.
+    pub fn delete_rows(&mut self, dq: &DeleteQuery) -> Result<usize, String> {
+        if self.references_to(&dq.table_name).next().is_none() {
+            return self
+                .get_table_mut(dq.table_name.to_string())
+                .delete_rows(dq);
+        }
+        let table = self.get_table(dq.table_name.to_string());
+        let rows = table.find_rows(dq.where_clause.as_ref())?;
+        let count = rows.len();
+
+        let mut plan = DeletePlan::default();
+        let table_name = table.name.to_string();
+        self.plan_delete(&table_name, rows, &mut plan)?;
+        self.all_or_nothing(&table_name, |db| {
+            for (table_name, column, rows) in plan.nulled {
+                let nulls = vec![None; rows.len()];
+                db.get_table_mut(table_name)
+                    .write_values(&rows, &[column], &[nulls])?;
+            }
+            for (table_name, rows) in plan.deleted {
+                let rows = rows.into_iter().collect::<Vec<usize>>();
+                db.get_table_mut(table_name).remove_rows(&rows);
+            }
+            Ok(())
+        })?;
+        Ok(count)
+    }
+
+    /// Runs `change`, which writes to the table `table_name` and the tables whose foreign keys
+    /// lead to it, and puts those tables back as they were when it fails part way.
+    fn all_or_nothing(
+        &mut self,
+        table_name: &str,
+        change: impl FnOnce(&mut Database) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut names = vec![table_name.to_string()];
+        let mut next = 0;
+        while next < names.len() {
+            let parent = names[next].to_string();
+            for (child, _) in self.references_to(&parent) {
+                if !names.iter().any(|n| n.eq_ignore_ascii_case(&child.name)) {
+                    names.push(child.name.to_string());
+                }
+            }
+            next += 1;
+        }
+        let saved = self
+            .tables
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| names.iter().any(|n| n.eq_ignore_ascii_case(&t.name)))
+            .map(|(pos, t)| (pos, t.clone()))
+            .collect::<Vec<(usize, Table)>>();
+        let result = change(self);
+        if result.is_err() {
+            // Tables are restored by position, a failed change may have renamed them.
+            for (pos, table) in saved {
+                self.tables[pos] = table;
+            }
+        }
+        result
+    }
+
+    /// Adds the deletion of `rows` from `table_name` to `plan`, along with what the ON DELETE
+    /// actions of the foreign keys referencing them call for.
+    fn plan_delete(
+        &self,
+        table_name: &str,
+        rows: Vec<usize>,
+        plan: &mut DeletePlan,
+    ) -> Result<(), String> {
+        let deleted = plan.deleted.entry(table_name.to_string()).or_default();
+        let rows = rows
+            .into_iter()
+            .filter(|row| deleted.insert(*row))
+            .collect::<Vec<usize>>();
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let table = self.get_table(table_name.to_string());
+        for (child, fk) in self.references_to(table_name) {
+            let keys = rows
+                .iter()
+                .filter_map(|row| table.get_value(&fk.parent_column, *row))
+                .collect::<HashSet<String>>();
+            let deleted = plan.deleted.get(&child.name);
+            let child_rows = child
+                .rows_holding(&fk.column, &keys)
+                .into_iter()
+                .filter(|row| deleted.is_none_or(|deleted| !deleted.contains(row)))
+                .collect::<Vec<usize>>();
+            if child_rows.is_empty() {
+                continue;
+            }
+            match fk.on_delete {
+                ForeignKeyAction::Restrict => {
+                    return Err(restricted("delete", table_name, &child.name, &fk.name))
+                }
+                ForeignKeyAction::Cascade => self.plan_delete(&child.name, child_rows, plan)?,
+                ForeignKeyAction::SetNull => {
+                    plan.nulled
+                        .push((child.name.to_string(), fk.column.to_string(), child_rows))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Every foreign key referencing the table `table_name`, with the table it belongs to.
+    fn references_to<'a>(
+        &'a self,
+        table_name: &'a str,
+    ) -> impl Iterator<Item = (&'a Table, &'a ForeignKey)> {
+        self.tables.iter().flat_map(move |child| {
+            child
+                .foreign_keys
+                .iter()
+                .filter(move |fk| fk.parent_table.eq_ignore_ascii_case(table_name))
+                .map(move |fk| (child, fk))
+        })
+    }
+
+    /// Fails when a foreign key references the column `col_name` of the table `table_name`.
+    fn check_unreferenced(
+        &self,
+        table_name: &str,
+        col_name: &str,
+        action: &str,
+    ) -> Result<(), String> {
+        match self
+            .references_to(table_name)
+            .find(|(_, fk)| fk.parent_column == col_name)
+        {
+            Some((child, fk)) => Err(format!(
+                "cannot {} column {}, foreign key {} of table {} references it",
+                action, col_name, fk.name, child.name
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails unless the parent table of `fk` holds `val`.
+    fn check_reference(&self, fk: &ForeignKey, val: &str) -> Result<(), String> {
+        let parent = self.get_table(fk.parent_table.to_string());
+        match parent.has_key(&fk.parent_column, val)? {
+            true => Ok(()),
+            false => Err(format!(
+                "Error: foreign key {} violated, {}.{} holds no {}",
+                fk.name, fk.parent_table, fk.parent_column, val
+            )),
+        }
+    }
+}
+
+/// The columns of a table made from `result`: named after the columns of the query, without
+/// their table, and nullable.
+fn result_columns(result: &QueryResult) -> Result<Vec<ParsedColumn>, String> {
+    let mut columns: Vec<ParsedColumn> = vec![];
+    for (label, datatype) in &result.columns {
+        let name = match label.contains('(') {
+            true => label.as_str(),
+            false => label.rsplit('.').next().unwrap(),
+        };
+        if columns.iter().any(|c| c.name == name) {
+            return Err(format!("the query returns the column {} twice", name));
+        }
+        columns.push(ParsedColumn {
+            name: name.to_string(),
+            datatype: datatype.type_name(),
+            is_pk: false,
+            is_nullable: true,
+            default: None,
+            is_auto_increment: false,
+        });
+    }
+    Ok(columns)
+}
+
+fn restricted(action: &str, table_name: &str, child: &str, fk: &str) -> String {
+    format!(
+        "Error: cannot {} rows of {}, rows of {} reference them through foreign key {}",
+        action, table_name, child, fk
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{alter::AlterTableQuery, insert::InsertQuery, select::SelectQuery};
+    use sqlparser::{ast::Statement, dialect::MySqlDialect, parser::Parser};
+
+    fn parse(query: &str) -> Statement {
+        Parser::parse_sql(&MySqlDialect {}, query)
+            .unwrap()
+            .remove(0)
+    }
+
+    fn create(db: &mut Database, query: &str) -> Result<(), String> {
+        CreateQuery::new(&parse(query)).and_then(|cq| db.create_table(cq))
+    }
+
+    /// A database holding the tables `queries` create.
+    fn database(queries: &[&str]) -> Database {
+        let mut db = Database::new();
+        for query in queries {
+            create(&mut db, query).unwrap();
+        }
+        db
+    }
+
+    fn insert(
+        db: &mut Database,
+        table_name: &str,
+        cols: &[&str],
+        values: &[i64],
+    ) -> Result<(), String> {
+        let cols = cols.iter().map(|c| c.to_string()).collect::<Vec<String>>();
+        let values = values.iter().map(|v| Some(v.to_string())).collect();
+        db.insert_rows(table_name, &cols, &[values])
+    }
+
+    fn insert_query(db: &mut Database, query: &str) -> Result<(), String> {
+        let iq = InsertQuery::new(&parse(query))?;
+        let values = match iq.select {
+            Some(sq) => db.select_values(sq, &iq.columns)?,
+            None => db.resolve_values(&iq.values)?,
+        };
+        db.insert_rows(&iq.table_name, &iq.columns, &values)
+    }
+
+    fn update(db: &mut Database, query: &str) -> Result<usize, String> {
+        UpdateQuery::new(&parse(query)).and_then(|uq| db.update_rows(&uq))
+    }
+
+    fn delete(db: &mut Database, query: &str) -> Result<usize, String> {
+        DeleteQuery::new(&parse(query)).and_then(|dq| db.delete_rows(&dq))
+    }
+
+    fn alter(db: &mut Database, query: &str) -> Result<(), String> {
+        AlterTableQuery::new(&parse(query)).and_then(|atq| db.alter_table(&atq))
+    }
+
+    fn column(db: &Database, query: &str) -> Vec<String> {
+        let sq = SelectQuery::new(&parse(query)).unwrap();
+        let table = db.get_table(sq.from.to_string());
+        table
+            .get_matching_rows(&sq)
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                table
+                    .get_value(sq.projection[0].column().unwrap(), row)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Customers with orders that follow them on delete and update, order details that lose
+    /// their order on delete, and notes that restrict both.
+    fn shop() -> Database {
+        let mut db = database(&[
+            "CREATE TABLE customers (id int PRIMARY KEY)",
+            "CREATE TABLE orders (id int PRIMARY KEY, customer_id int REFERENCES customers \
+             ON DELETE CASCADE ON UPDATE CASCADE)",
+            "CREATE TABLE details (id int PRIMARY KEY, order_id int, \
+             FOREIGN KEY (order_id) REFERENCES orders (id) ON DELETE SET NULL)",
+            "CREATE TABLE notes (id int PRIMARY KEY, customer_id int REFERENCES customers (id))",
+        ]);
+        for id in [1, 2, 3] {
+            insert(&mut db, "customers", &["id"], &[id]).unwrap();
+        }
+        insert(&mut db, "orders", &["id", "customer_id"], &[10, 1]).unwrap();
+        insert(&mut db, "orders", &["id", "customer_id"], &[11, 2]).unwrap();
+        insert(&mut db, "details", &["id", "order_id"], &[100, 10]).unwrap();
+        insert(&mut db, "details", &["id", "order_id"], &[101, 11]).unwrap();
+        insert(&mut db, "notes", &["id", "customer_id"], &[1000, 3]).unwrap();
+        db
+    }
+
+    #[test]
+    fn tests_foreign_key_parent_must_exist() {
+        let mut db = shop();
+        assert!(create(&mut db, "CREATE TABLE t (a int REFERENCES nope (id))").is_err());
+        assert!(insert(&mut db, "orders", &["id", "customer_id"], &[12, 4]).is_err());
+        assert!(update(&mut db, "UPDATE orders SET customer_id = 7 WHERE id = 10").is_err());
+        assert_eq!(column(&db, "SELECT id FROM orders"), vec!["10", "11"]);
+    }
+
+    #[test]
+    fn tests_restrict_refuses_referenced_changes() {
+        let mut db = shop();
+        // Notes restrict changes to the customers they are about.
+        assert!(delete(&mut db, "DELETE FROM customers WHERE id = 3").is_err());
+        assert!(update(&mut db, "UPDATE customers SET id = 5 WHERE id = 3").is_err());
+        assert_eq!(column(&db, "SELECT id FROM customers"), vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn tests_update_cascades_to_referencing_rows() {
+        let mut db = shop();
+        update(&mut db, "UPDATE customers SET id = 5 WHERE id = 2").unwrap();
+        assert_eq!(
+            column(&db, "SELECT customer_id FROM orders"),
+            vec!["1", "5"]
+        );
+    }
+
+    #[test]
+    fn tests_delete_cascades_and_sets_null() {
+        let mut db = shop();
+        assert_eq!(delete(&mut db, "DELETE FROM customers WHERE id = 1"), Ok(1));
+        assert_eq!(column(&db, "SELECT id FROM orders"), vec!["11"]);
+        assert_eq!(column(&db, "SELECT order_id FROM details"), vec!["", "11"]);
+        assert_eq!(column(&db, "SELECT id FROM customers"), vec!["2", "3"]);
+    }
+
+    #[test]
+    fn tests_failed_cascade_changes_nothing() {
+        let mut db = database(&[
+            "CREATE TABLE p (id int PRIMARY KEY)",
+            "CREATE TABLE c (id int PRIMARY KEY, pid int REFERENCES p ON UPDATE CASCADE, \
+             CHECK (pid < 50))",
+        ]);
+        insert(&mut db, "p", &["id"], &[1]).unwrap();
+        insert(&mut db, "c", &["id", "pid"], &[10, 1]).unwrap();
+
+        assert!(update(&mut db, "UPDATE p SET id = 60 WHERE id = 1").is_err());
+        assert_eq!(column(&db, "SELECT id FROM p"), vec!["1"]);
+        assert_eq!(column(&db, "SELECT pid FROM c"), vec!["1"]);
+    }
+
+    fn products() -> Database {
+        database(&["CREATE TABLE products (id int AUTO_INCREMENT PRIMARY KEY, price int)"])
+    }
+
+    #[test]
+    fn tests_auto_increment_needs_an_integer_column() {
+        let mut db = Database::new();
+        assert!(create(&mut db, "CREATE TABLE t (a text AUTO_INCREMENT)").is_err());
+    }
+
+    #[test]
+    fn tests_auto_increment_fills_missing_and_null_values() {
+        let mut db = products();
+        for query in [
+            "INSERT INTO products (price) VALUES (10), (20)",
+            "INSERT INTO products (id, price) VALUES (10, 30)",
+            "INSERT INTO products (id, price) VALUES (NULL, 40)",
+        ] {
+            insert_query(&mut db, query).unwrap();
+        }
+        assert_eq!(
+            column(&db, "SELECT id FROM products"),
+            vec!["1", "2", "10", "11"]
+        );
+    }
+
+    #[test]
+    fn tests_auto_increment_never_reuses_deleted_values() {
+        let mut db = products();
+        insert_query(
+            &mut db,
+            "INSERT INTO products (price) VALUES (10), (20), (30)",
+        )
+        .unwrap();
+        delete(&mut db, "DELETE FROM products WHERE price >= 20").unwrap();
+        insert(&mut db, "products", &["price"], &[70]).unwrap();
+        assert_eq!(column(&db, "SELECT id FROM products"), vec!["1", "4"]);
+    }
+
+    #[test]
+    fn tests_serial_starts_at_the_table_auto_increment() {
+        let mut db =
+            database(&["CREATE TABLE suppliers (id serial, name text) AUTO_INCREMENT = 100"]);
+        insert_query(&mut db, "INSERT INTO suppliers (name) VALUES ('a'), ('b')").unwrap();
+        assert_eq!(column(&db, "SELECT id FROM suppliers"), vec!["100", "101"]);
+    }
+
+    #[test]
+    fn tests_sequences() {
+        let mut db = products();
+        let csq =
+            CreateSequenceQuery::new(&parse("CREATE SEQUENCE seq INCREMENT BY 5 START WITH 20"))
+                .unwrap();
+        db.create_sequence(&csq).unwrap();
+        assert!(db.create_sequence(&csq).is_err());
+
+        insert_query(
+            &mut db,
+            "INSERT INTO products (id, price) VALUES (nextval('seq'), 50), (nextval('seq'), 60)",
+        )
+        .unwrap();
+        assert_eq!(column(&db, "SELECT id FROM products"), vec!["20", "25"]);
+        // The AUTO_INCREMENT column moves past values a sequence gave it.
+        insert(&mut db, "products", &["price"], &[70]).unwrap();
+        assert_eq!(
+            column(&db, "SELECT id FROM products"),
+            vec!["20", "25", "26"]
+        );
+
+        assert!(insert_query(&mut db, "INSERT INTO products VALUES (nextval('nope'), 1)").is_err());
+        assert!(db.drop_sequence("seq"));
+        assert!(!db.drop_sequence("seq"));
+    }
+
+    #[test]
+    fn tests_drop_and_truncate() {
+        let mut db = database(&[
+            "CREATE TABLE customers (id int PRIMARY KEY, name text)",
+            "CREATE TABLE orders (id int PRIMARY KEY, customer_id int REFERENCES customers)",
+        ]);
+        for id in [1, 2] {
+            insert(&mut db, "customers", &["id"], &[id]).unwrap();
+        }
+        insert(&mut db, "orders", &["id", "customer_id"], &[10, 1]).unwrap();
+
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert!(db.truncate_table("customers").is_err());
+        assert!(db.drop_tables(&names(&["customers"]), false).is_err());
+        assert!(db.drop_tables(&names(&["nope"]), false).is_err());
+        db.drop_tables(&names(&["nope"]), true).unwrap();
+
+        assert_eq!(db.truncate_table("orders"), Ok(1));
+        assert!(column(&db, "SELECT id FROM orders").is_empty());
+        insert(&mut db, "orders", &["id", "customer_id"], &[10, 2]).unwrap();
+        assert_eq!(
+            column(&db, "SELECT id FROM orders WHERE id = 10"),
+            vec!["10"]
+        );
+
+        db.drop_tables(&names(&["orders"]), false).unwrap();
+        assert!(!db.table_exists("orders".to_string()));
+        assert_eq!(db.truncate_table("customers"), Ok(2));
+        db.drop_tables(&names(&["customers"]), false).unwrap();
+        assert!(db.tables.is_empty());
+    }
+
+    #[test]
+    fn tests_truncate_restarts_auto_increment() {
+        let mut db = database(&[
+            "CREATE TABLE products (id int AUTO_INCREMENT PRIMARY KEY, price int)",
+            "CREATE TABLE suppliers (id serial, price int) AUTO_INCREMENT = 100",
+        ]);
+        for table_name in ["products", "suppliers"] {
+            for price in [10, 20] {
+                insert(&mut db, table_name, &["price"], &[price]).unwrap();
+            }
+            assert_eq!(db.truncate_table(table_name), Ok(2));
+            insert(&mut db, table_name, &["price"], &[30]).unwrap();
+        }
+        assert_eq!(column(&db, "SELECT id FROM products"), vec!["1"]);
+        assert_eq!(column(&db, "SELECT id FROM suppliers"), vec!["100"]);
+    }
+
+    /// Products with a unique code, and items referencing them.
+    fn catalog() -> Database {
+        let mut db = database(&[
+            "CREATE TABLE products (id int PRIMARY KEY, code text UNIQUE, price decimal(6,2))",
+            "CREATE TABLE items (id int PRIMARY KEY, product_id int REFERENCES products)",
+        ]);
+        for query in [
+            "INSERT INTO products (id, code, price) VALUES (1, '10', 1.5), (2, '20', 2.25)",
+            "INSERT INTO items (id, product_id) VALUES (1, 2)",
+        ] {
+            insert_query(&mut db, query).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn tests_add_column_gives_existing_rows_its_default() {
+        let mut db = catalog();
+        assert!(alter(&mut db, "ALTER TABLE products ADD COLUMN qty int NOT NULL").is_err());
+        alter(
+            &mut db,
+            "ALTER TABLE products ADD COLUMN qty int NOT NULL DEFAULT 5",
+        )
+        .unwrap();
+        assert_eq!(column(&db, "SELECT qty FROM products"), vec!["5", "5"]);
+    }
+
+    #[test]
+    fn tests_add_column_checks_its_default_on_an_empty_table() {
+        let mut db = database(&["CREATE TABLE products (id int PRIMARY KEY)"]);
+        assert!(alter(
+            &mut db,
+            "ALTER TABLE products ADD COLUMN qty int DEFAULT 'many'"
+        )
+        .is_err());
+        assert!(!db
+            .get_table("products".to_string())
+            .column_exist("qty".to_string()));
+    }
+
+    #[test]
+    fn tests_change_type_needs_every_value_to_fit() {
+        let mut db = catalog();
+        assert!(alter(&mut db, "ALTER TABLE products CHANGE price price int").is_err());
+        assert!(alter(
+            &mut db,
+            "ALTER TABLE products CHANGE price price decimal(6,1)"
+        )
+        .is_err());
+        alter(&mut db, "ALTER TABLE products CHANGE price price float").unwrap();
+        alter(&mut db, "ALTER TABLE products CHANGE code code int").unwrap();
+        assert_eq!(
+            column(&db, "SELECT id FROM products WHERE code = 20"),
+            vec!["2"]
+        );
+    }
+
+    #[test]
+    fn tests_change_type_refuses_foreign_key_columns() {
+        let mut db = catalog();
+        assert!(alter(
+            &mut db,
+            "ALTER TABLE items CHANGE product_id product_id bigint"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn tests_drop_column_takes_its_indexes() {
+        let mut db = catalog();
+        assert!(alter(&mut db, "ALTER TABLE products DROP COLUMN id").is_err());
+        alter(&mut db, "ALTER TABLE products DROP COLUMN code").unwrap();
+        alter(&mut db, "ALTER TABLE products DROP COLUMN IF EXISTS code").unwrap();
+        assert!(!db
+            .get_table("products".to_string())
+            .has_index("products_code_idx"));
+    }
+
+    #[test]
+    fn tests_renames_carry_over_to_foreign_keys() {
+        let mut db = catalog();
+        alter(
+            &mut db,
+            "ALTER TABLE products RENAME COLUMN id TO product_id",
+        )
+        .unwrap();
+        alter(&mut db, "ALTER TABLE products RENAME TO goods").unwrap();
+        assert!(!db.table_exists("products".to_string()));
+        assert_eq!(column(&db, "SELECT product_id FROM goods"), vec!["1", "2"]);
+        assert!(insert(&mut db, "items", &["id", "product_id"], &[2, 3]).is_err());
+        insert(&mut db, "items", &["id", "product_id"], &[2, 1]).unwrap();
+    }
+
+    #[test]
+    fn tests_failed_alter_table_changes_nothing() {
+        let mut db = catalog();
+        assert!(alter(
+            &mut db,
+            "ALTER TABLE products RENAME COLUMN id TO product_id, RENAME TO goods, \
+             ADD COLUMN qty int DEFAULT 'many'"
+        )
+        .is_err());
+        assert!(db.table_exists("products".to_string()));
+        assert!(!db.table_exists("goods".to_string()));
+        let products = db.get_table("products".to_string());
+        assert!(products.column_exist("id".to_string()));
+        assert!(!products.column_exist("qty".to_string()));
+        let fk = &db.get_table("items".to_string()).foreign_keys[0];
+        assert_eq!(
+            (fk.parent_table.as_str(), fk.parent_column.as_str()),
+            ("products", "id")
+        );
+    }
+
+    #[test]
+    fn tests_duplicate_table_names() {
+        let mut db = Database::new();
+        create(&mut db, "CREATE TABLE Customers (id int PRIMARY KEY)").unwrap();
+        assert!(create(&mut db, "CREATE TABLE customers (id int PRIMARY KEY)").is_err());
+        create(&mut db, "CREATE TABLE IF NOT EXISTS CUSTOMERS (name text)").unwrap();
+        assert_eq!(db.tables.len(), 1);
+        assert!(db
+            .get_table("customers".to_string())
+            .column_exist("id".to_string()));
+
+        create(
+            &mut db,
+            "CREATE TABLE orders (id int PRIMARY KEY, customer_id int REFERENCES CUSTOMERS)",
+        )
+        .unwrap();
+        assert_eq!(
+            db.get_table("ORDERS".to_string()).foreign_keys[0].parent_table,
+            "Customers"
+        );
+        insert(&mut db, "CUSTOMERS", &["id"], &[1]).unwrap();
+        insert(&mut db, "Orders", &["id", "customer_id"], &[10, 1]).unwrap();
+        assert!(delete(&mut db, "DELETE FROM customers").is_err());
+        assert_eq!(column(&db, "SELECT id FROM ORDERS"), vec!["10"]);
+    }
+
+    /// A database with a table of items to select from.
+    fn items() -> Database {
+        let mut db =
+            database(&["CREATE TABLE items (id int PRIMARY KEY, kind text, price decimal(6,2))"]);
+        insert_query(
+            &mut db,
+            "INSERT INTO items (id, kind, price) VALUES (1, 'a', 1.50), (2, 'b', 2.25), \
+             (3, 'a', 3.00)",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn tests_create_table_as_select_takes_the_query_columns() {
+        let mut db = items();
+        create(
+            &mut db,
+            "CREATE TABLE totals AS SELECT kind, COUNT(*), SUM(price) FROM items GROUP BY kind",
+        )
+        .unwrap();
+        let totals = db.get_table("totals".to_string());
+        let types = totals
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.datatype.type_name()))
+            .collect::<Vec<(&str, String)>>();
+        assert_eq!(
+            types,
+            vec![
+                ("kind", "string".to_string()),
+                ("COUNT(*)", "bigint".to_string()),
+                ("SUM(price)", "decimal(38,2)".to_string()),
+            ]
+        );
+        assert_eq!(column(&db, "SELECT kind FROM totals"), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn tests_create_table_as_select_rejects_repeated_columns() {
+        let mut db = items();
+        assert!(create(&mut db, "CREATE TABLE twice AS SELECT id, id FROM items").is_err());
+        assert!(!db.table_exists("twice".to_string()));
+    }
+
+    #[test]
+    fn tests_insert_select_inserts_the_query_rows() {
+        let mut db = items();
+        create(&mut db, "CREATE TABLE cheap (id bigint, kind text)").unwrap();
+        insert_query(
+            &mut db,
+            "INSERT INTO cheap (id, kind) SELECT id, kind FROM items WHERE price < 3",
+        )
+        .unwrap();
+        assert_eq!(column(&db, "SELECT id FROM cheap"), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn tests_insert_select_checks_the_column_count() {
+        let mut db = items();
+        create(&mut db, "CREATE TABLE cheap (id bigint, kind text)").unwrap();
+        assert!(
+            insert_query(&mut db, "INSERT INTO cheap (id) SELECT id, kind FROM items").is_err()
+        );
+        assert!(column(&db, "SELECT id FROM cheap").is_empty());
+    }
 }
@@ -8,15 +8,19 @@ use std::fs::File;
 use std::io::{prelude::Write, stdin, stdout, BufWriter, Read};
 
 use database::Database;
+use parser::alter::AlterTableQuery;
 use parser::create::CreateQuery;
+use parser::delete::DeleteQuery;
+use parser::drop::{DropTableQuery, TruncateQuery};
+use parser::index::{CreateIndexQuery, DropIndexQuery};
 use parser::insert::InsertQuery;
 use parser::select::SelectQuery;
-use table::Table;
+use parser::sequence::{CreateSequenceQuery, DropSequenceQuery};
+use parser::update::UpdateQuery;
 
-use sqlparser::ast::Statement;
+use sqlparser::ast::{ObjectType, Statement};
 use sqlparser::dialect::MySqlDialect;
 use sqlparser::parser::Parser;
-use sqlparser::tokenizer::Tokenizer;
 
 mod database;
 mod parser;
@@ -44,38 +48,15 @@ impl MetaCommand {
     }
 }
 
-enum DbCommand {
-    Insert(String),
-    Delete(String),
-    Update(String),
-    CreateTable(String),
-    Select(String),
-    Unknown(String),
-}
-
-impl DbCommand {
-    fn new(command: String) -> DbCommand {
-        let v = command.split(" ").collect::<Vec<&str>>();
-        match v[0] {
-            "insert" => DbCommand::Insert(command),
-            "update" => DbCommand::Update(command),
-            "delete" => DbCommand::Delete(command),
-            "create" => DbCommand::CreateTable(command),
-            "select" => DbCommand::Select(command),
-            _ => DbCommand::Unknown(command),
-        }
-    }
-}
-
 enum CommandType {
     MetaCommand(MetaCommand),
-    DbCommand(DbCommand),
+    DbCommand(String),
 }
 
 fn get_command_type(cmd: &String) -> CommandType {
     match cmd.starts_with(".") {
         true => CommandType::MetaCommand(MetaCommand::new(cmd.to_owned())),
-        false => CommandType::DbCommand(DbCommand::new(cmd.to_owned())),
+        false => CommandType::DbCommand(cmd.to_owned()),
     }
 }
 
@@ -83,7 +64,7 @@ fn handle_meta_command(cmd: MetaCommand, db: &mut Database) {
     match cmd {
         MetaCommand::Exit => std::process::exit(0),
         MetaCommand::ListTables => {
-            if db.tables.len() == 0 {
+            if db.tables.is_empty() {
                 println!("No tables found");
             }
             for table in &db.tables {
@@ -111,14 +92,20 @@ fn handle_meta_command(cmd: MetaCommand, db: &mut Database) {
 
 fn process_command(query: String, db: &mut Database) {
     let dialect = MySqlDialect {};
-    let statements = &Parser::parse_sql(&dialect, query).unwrap();
+    let statements = match Parser::parse_sql(&dialect, &query) {
+        Ok(statements) => statements,
+        Err(e) => {
+            println!("Error while trying to parse query: {}", e);
+            return;
+        }
+    };
 
-    for s in statements {
-        println!("{:?}", s);
+    for s in &statements {
         match s {
             Statement::CreateTable { .. } => {
-                let cq = CreateQuery::new(s).unwrap();
-                db.tables.push(Table::new(cq));
+                if let Err(err) = CreateQuery::new(s).and_then(|cq| db.create_table(cq)) {
+                    println!("Cannot create table: {}", err);
+                }
             }
             Statement::Insert { .. } => {
                 let iq = InsertQuery::new(s);
@@ -127,24 +114,20 @@ fn process_command(query: String, db: &mut Database) {
                         let table_name = iq.table_name;
                         let columns = iq.columns;
                         let values = iq.values;
-                        println!("cols = {:?}\n vals = {:?}", columns, values);
+                        let select = iq.select;
                         match db.table_exists(table_name.to_string()) {
                             true => {
-                                let db_table = db.get_table_mut(table_name.to_string());
+                                let db_table = db.get_table(table_name.to_string());
                                 match columns.iter().all(|c| db_table.column_exist(c.to_string())) {
                                     true => {
-                                        for value in &values {
-                                            match db_table
-                                                .does_violate_unique_constraint(&columns, value)
-                                            {
-                                                Err(err) => println!(
-                                                    "Unique key constaint violation: {}",
-                                                    err
-                                                ),
-                                                Ok(()) => {
-                                                    db_table.insert_row(&columns, &values);
-                                                }
-                                            }
+                                        let values = match select {
+                                            Some(sq) => db.select_values(sq, &columns),
+                                            None => db.resolve_values(&values),
+                                        };
+                                        if let Err(err) = values.and_then(|values| {
+                                            db.insert_rows(&table_name, &columns, &values)
+                                        }) {
+                                            println!("Cannot insert row: {}", err);
                                         }
                                     }
                                     false => {
@@ -158,50 +141,139 @@ fn process_command(query: String, db: &mut Database) {
                     Err(err) => println!("Error while trying to parse insert statement: {}", err),
                 }
             }
-            Statement::Query(_q) => {
-                let select_query = SelectQuery::new(&s);
-                match select_query {
-                    Ok(mut sq) => match db.table_exists((&sq.from).to_string()) {
-                        true => {
-                            let db_table = db.get_table((&sq.from).to_string());
-
-                            let cloned_projection = sq.projection.clone();
-
-                            for p in &cloned_projection {
-                                if p == "*" {
-                                    let new_projections = db_table
-                                        .columns
-                                        .iter()
-                                        .map(|c| c.name.to_string())
-                                        .collect::<Vec<String>>();
-                                    sq.insert_projections(new_projections);
-                                }
-                            }
-
-                            for col in &sq.projection {
-                                if !db_table.column_exist((&col).to_string()) {
-                                    println!(
-                                        "Cannot execute query, cannot find column {} in table {}",
-                                        col, db_table.name
-                                    );
-                                    return;
-                                }
-                            }
-
-                            println!("sq = {:?}", &sq);
-                            db_table.execute_select_query(&sq);
+            Statement::Query(_q) => match SelectQuery::new(s) {
+                Ok(sq) => match db.query(sq) {
+                    Ok(result) => result.print(),
+                    Err(err) => println!("Error while trying to execute select query: {}", err),
+                },
+                Err(error) => println!("{}", error),
+            },
+            Statement::Update { .. } => match UpdateQuery::new(s) {
+                Ok(uq) => match db.table_exists(uq.table_name.to_string()) {
+                    true => match db.update_rows(&uq) {
+                        Ok(count) => println!("{} row(s) updated", count),
+                        Err(err) => println!("Error while trying to execute update: {}", err),
+                    },
+                    false => println!("Table doesn't exist"),
+                },
+                Err(err) => println!("Error while trying to parse update statement: {}", err),
+            },
+            Statement::Delete { .. } => match DeleteQuery::new(s) {
+                Ok(dq) => match db.table_exists(dq.table_name.to_string()) {
+                    true => match db.delete_rows(&dq) {
+                        Ok(count) => println!("{} row(s) deleted", count),
+                        Err(err) => println!("Error while trying to execute delete: {}", err),
+                    },
+                    false => println!("Table doesn't exist"),
+                },
+                Err(err) => println!("Error while trying to parse delete statement: {}", err),
+            },
+            Statement::CreateIndex { .. } => match CreateIndexQuery::new(s) {
+                Ok(ciq) => match db.table_exists(ciq.table_name.to_string()) {
+                    true if db.get_index_table_mut(&ciq.name).is_some() => {
+                        if !ciq.if_not_exists {
+                            println!("Index {} already exists", ciq.name);
+                        }
+                    }
+                    true => {
+                        let db_table = db.get_table_mut(ciq.table_name.to_string());
+                        match db_table.create_index(&ciq) {
+                            Ok(()) => println!("Index {} created", ciq.name),
+                            Err(err) => println!("Error while trying to create index: {}", err),
                         }
-                        false => {
-                            println!("Cannot execute query the table {} doesn't exists", sq.from);
+                    }
+                    false => println!("Table doesn't exist"),
+                },
+                Err(err) => println!(
+                    "Error while trying to parse create index statement: {}",
+                    err
+                ),
+            },
+            Statement::Drop {
+                object_type: ObjectType::Index,
+                ..
+            } => match DropIndexQuery::new(s) {
+                Ok(diq) => {
+                    for name in &diq.names {
+                        match db.get_index_table_mut(name) {
+                            Some(db_table) => match db_table.drop_index(name) {
+                                Ok(()) => println!("Index {} dropped", name),
+                                Err(err) => println!("Error while trying to drop index: {}", err),
+                            },
+                            None if diq.if_exists => {}
+                            None => println!("Index {} doesn't exist", name),
                         }
+                    }
+                }
+                Err(err) => println!("Error while trying to parse drop index statement: {}", err),
+            },
+            Statement::Drop {
+                object_type: ObjectType::Table,
+                ..
+            } => match DropTableQuery::new(s) {
+                Ok(dtq) => match db.drop_tables(&dtq.names, dtq.if_exists) {
+                    Ok(()) => println!("Table(s) dropped"),
+                    Err(err) => println!("Error while trying to drop table: {}", err),
+                },
+                Err(err) => println!("Error while trying to parse drop table statement: {}", err),
+            },
+            Statement::Truncate { .. } => match TruncateQuery::new(s) {
+                Ok(tq) => match db.table_exists(tq.table_name.to_string()) {
+                    true => match db.truncate_table(&tq.table_name) {
+                        Ok(count) => println!("{} row(s) deleted", count),
+                        Err(err) => println!("Error while trying to truncate table: {}", err),
                     },
-                    Err(error) => {
-                        println!("{}", error);
+                    false => println!("Table doesn't exist"),
+                },
+                Err(err) => println!("Error while trying to parse truncate statement: {}", err),
+            },
+            Statement::AlterTable { .. } => match AlterTableQuery::new(s) {
+                Ok(atq) => match db.table_exists(atq.table_name.to_string()) {
+                    true => match db.alter_table(&atq) {
+                        Ok(()) => println!("Table {} altered", atq.table_name),
+                        Err(err) => println!("Error while trying to alter table: {}", err),
+                    },
+                    false => println!("Table doesn't exist"),
+                },
+                Err(err) => println!("Error while trying to parse alter table statement: {}", err),
+            },
+            Statement::CreateSequence { .. } => match CreateSequenceQuery::new(s) {
+                Ok(csq) if db.sequence_exists(&csq.name) => {
+                    if !csq.if_not_exists {
+                        println!("Sequence {} already exists", csq.name);
                     }
                 }
-            }
+                Ok(csq) => match db.create_sequence(&csq) {
+                    Ok(()) => println!("Sequence {} created", csq.name),
+                    Err(err) => println!("Error while trying to create sequence: {}", err),
+                },
+                Err(err) => println!(
+                    "Error while trying to parse create sequence statement: {}",
+                    err
+                ),
+            },
+            Statement::Drop {
+                object_type: ObjectType::Sequence,
+                ..
+            } => match DropSequenceQuery::new(s) {
+                Ok(dsq) => {
+                    for name in &dsq.names {
+                        match db.drop_sequence(name) {
+                            true => println!("Sequence {} dropped", name),
+                            false if dsq.if_exists => {}
+                            false => println!("Sequence {} doesn't exist", name),
+                        }
+                    }
+                }
+                Err(err) => println!(
+                    "Error while trying to parse drop sequence statement: {}",
+                    err
+                ),
+            },
             _ => {
-                println!("Not a insert, create table or select query");
+                println!(
+                    "Not a select, insert, update, delete, create, alter, drop or truncate statement"
+                );
             }
         }
     }
@@ -217,15 +289,6 @@ fn main() {
             Ok(mut file) => {
                 let mut query = String::new();
                 file.read_to_string(&mut query).unwrap();
-
-                let dialect = MySqlDialect {};
-                let mut tokenizer = Tokenizer::new(&dialect, &query);
-                match tokenizer.tokenize() {
-                    Ok(t) => {
-                        println!("{:?}", t);
-                    }
-                    Err(e) => println!("err {:?}", e),
-                }
                 process_command(query, &mut db);
 
                 println!("query processed");
@@ -244,8 +307,8 @@ fn main() {
             .expect("Error while trying to read from stdin");
 
         match get_command_type(&command.trim().to_owned()) {
-            CommandType::DbCommand(_cmd) => {
-                process_command(command.trim().to_string(), &mut db);
+            CommandType::DbCommand(cmd) => {
+                process_command(cmd, &mut db);
             }
             CommandType::MetaCommand(cmd) => {
                 handle_meta_command(cmd, &mut db);
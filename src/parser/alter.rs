@@ -0,0 +1,144 @@
+use sqlparser::ast::{
+    AlterColumnOperation, AlterTableOperation, ColumnDef, ColumnOption, DataType, Statement,
+};
+
+use crate::parser::create::{column_type, default_value, ParsedColumn};
+
+#[derive(PartialEq, Debug)]
+pub enum AlterOperation {
+    AddColumn(ParsedColumn),
+    DropColumn {
+        name: String,
+        if_exists: bool,
+    },
+    RenameColumn {
+        old_name: String,
+        new_name: String,
+    },
+    RenameTable(String),
+    /// Gives the column a new type, converting the values it holds.
+    ChangeType {
+        column: String,
+        datatype: String,
+    },
+}
+
+#[derive(Debug)]
+pub struct AlterTableQuery {
+    pub table_name: String,
+    pub operations: Vec<AlterOperation>, // applied in order
+}
+
+impl AlterTableQuery {
+    pub fn new(statement: &Statement) -> Result<AlterTableQuery, String> {
+        match statement {
+            Statement::AlterTable {
+                name, operations, ..
+            } => {
+                let mut parsed_operations = vec![];
+                for operation in operations {
+                    match operation {
+                        AlterTableOperation::AddColumn { column_def, .. } => {
+                            parsed_operations
+                                .push(AlterOperation::AddColumn(added_column(column_def)?));
+                        }
+                        AlterTableOperation::DropColumn {
+                            column_name,
+                            if_exists,
+                            ..
+                        } => parsed_operations.push(AlterOperation::DropColumn {
+                            name: column_name.value.to_string(),
+                            if_exists: *if_exists,
+                        }),
+                        AlterTableOperation::RenameColumn {
+                            old_column_name,
+                            new_column_name,
+                        } => parsed_operations.push(AlterOperation::RenameColumn {
+                            old_name: old_column_name.value.to_string(),
+                            new_name: new_column_name.value.to_string(),
+                        }),
+                        AlterTableOperation::RenameTable { table_name } => parsed_operations
+                            .push(AlterOperation::RenameTable(table_name.to_string())),
+                        // MySQL's CHANGE [COLUMN] old_name new_name type.
+                        AlterTableOperation::ChangeColumn {
+                            old_name,
+                            new_name,
+                            data_type,
+                            options,
+                        } => {
+                            if let Some(option) = options.first() {
+                                return Err(format!(
+                                    "Unsupported option {} in CHANGE COLUMN",
+                                    option
+                                ));
+                            }
+                            parsed_operations.push(AlterOperation::ChangeType {
+                                column: old_name.value.to_string(),
+                                datatype: changed_type(data_type)?,
+                            });
+                            if old_name.value != new_name.value {
+                                parsed_operations.push(AlterOperation::RenameColumn {
+                                    old_name: old_name.value.to_string(),
+                                    new_name: new_name.value.to_string(),
+                                });
+                            }
+                        }
+                        AlterTableOperation::AlterColumn {
+                            column_name,
+                            op: AlterColumnOperation::SetDataType { data_type, .. },
+                        } => parsed_operations.push(AlterOperation::ChangeType {
+                            column: column_name.value.to_string(),
+                            datatype: changed_type(data_type)?,
+                        }),
+                        _ => return Err(format!("Unsupported operation {}", operation)),
+                    }
+                }
+
+                Ok(AlterTableQuery {
+                    table_name: name.to_string(),
+                    operations: parsed_operations,
+                })
+            }
+            _ => Err("Error parsing alter table query".to_string()),
+        }
+    }
+}
+
+/// The column an ADD COLUMN appends. Keys and constraints are left to CREATE INDEX.
+fn added_column(column_def: &ColumnDef) -> Result<ParsedColumn, String> {
+    let name = column_def.name.value.to_string();
+    let (datatype, is_serial) = column_type(&column_def.data_type)?;
+    if is_serial {
+        return Err(format!("Cannot add the SERIAL column {}", name));
+    }
+    let mut is_nullable = true;
+    let mut default = None;
+    for column_option in &column_def.options {
+        match &column_option.option {
+            ColumnOption::NotNull => is_nullable = false,
+            ColumnOption::Null => is_nullable = true,
+            ColumnOption::Default(expr) => default = default_value(expr, &name)?,
+            option => {
+                return Err(format!(
+                    "Unsupported option {} for the added column {}",
+                    option, name
+                ))
+            }
+        }
+    }
+    Ok(ParsedColumn {
+        name,
+        datatype,
+        is_pk: false,
+        is_nullable,
+        default,
+        is_auto_increment: false,
+    })
+}
+
+fn changed_type(data_type: &DataType) -> Result<String, String> {
+    match column_type(data_type)? {
+        (_, true) => Err("Cannot change a column to SERIAL".to_string()),
+        (datatype, false) => Ok(datatype),
+    }
+}
@@ -1,4 +1,10 @@
-use sqlparser::ast::{ColumnOption, DataType, ObjectName, Statement};
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{
+    ColumnOption, DataType, ExactNumberInfo, Expr, Ident, ObjectName, ReferentialAction, Statement,
+    TableConstraint, Value,
+};
+
+use crate::parser::select::{literal_to_string, Predicate, SelectQuery};
 
 #[derive(PartialEq, Debug)]
 pub struct ParsedColumn {
@@ -6,12 +12,71 @@ pub struct ParsedColumn {
     pub datatype: String,
     pub is_pk: bool,
     pub is_nullable: bool,
+    pub default: Option<String>, // None when the column defaults to NULL
+    pub is_auto_increment: bool,
+}
+
+/// An index declared along with the table, e.g. `KEY by_name (last_name, first_name)`.
+#[derive(PartialEq, Debug)]
+pub struct ParsedIndex {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+}
+
+/// A `CHECK (expr)` constraint, given on a column or on the table.
+#[derive(PartialEq, Debug)]
+pub struct ParsedCheck {
+    pub name: String,
+    pub expr: String, // the condition as written, for display
+    pub predicate: Predicate,
+}
+
+/// What a foreign key does to the rows referencing a parent row when that row is deleted or
+/// its key changes.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub enum ForeignKeyAction {
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+impl ForeignKeyAction {
+    fn new(action: &Option<ReferentialAction>) -> Result<ForeignKeyAction, String> {
+        match action {
+            // Checks are never deferred, so NO ACTION is the same as RESTRICT.
+            None | Some(ReferentialAction::Restrict) | Some(ReferentialAction::NoAction) => {
+                Ok(ForeignKeyAction::Restrict)
+            }
+            Some(ReferentialAction::Cascade) => Ok(ForeignKeyAction::Cascade),
+            Some(ReferentialAction::SetNull) => Ok(ForeignKeyAction::SetNull),
+            Some(action) => Err(format!("Unsupported foreign key action {}", action)),
+        }
+    }
+}
+
+/// A `REFERENCES parent (column)` on a column or a table-level `FOREIGN KEY`.
+#[derive(PartialEq, Debug)]
+pub struct ParsedForeignKey {
+    pub name: String,
+    pub column: String,
+    pub parent_table: String,
+    pub parent_column: Option<String>, // None for the primary key of the parent
+    pub on_delete: ForeignKeyAction,
+    pub on_update: ForeignKeyAction,
 }
 
 #[derive(Debug)]
 pub struct CreateQuery {
     pub table_name: String,         // table name
     pub columns: Vec<ParsedColumn>, // columns that will be fetched
+    pub primary_key: Vec<String>,   // a primary key over several columns, empty otherwise
+    pub indexes: Vec<ParsedIndex>,
+    pub checks: Vec<ParsedCheck>,
+    pub foreign_keys: Vec<ParsedForeignKey>,
+    pub auto_increment_start: Option<u32>, // the `AUTO_INCREMENT = n` table option
+    pub if_not_exists: bool,
+    pub query: Option<SelectQuery>, // CREATE TABLE ... AS SELECT, which gives the columns
 }
 
 impl CreateQuery {
@@ -20,71 +85,341 @@ impl CreateQuery {
             Statement::CreateTable {
                 name,
                 columns,
-                constraints: _constraints,
-                with_options: _with_options,
-                external: _external,
-                file_format: _file_format,
-                location: _location,
+                constraints,
+                auto_increment_offset,
+                if_not_exists,
+                query,
+                ..
             } => {
                 let table_name = name;
+                if query.is_some() && (!columns.is_empty() || !constraints.is_empty()) {
+                    return Err(format!(
+                        "Table {} takes its columns from the query, they cannot be listed",
+                        table_name
+                    ));
+                }
                 let mut parsed_columns: Vec<ParsedColumn> = vec![];
+                let mut indexes = vec![];
+                let mut checks = vec![];
+                let mut foreign_keys = vec![];
 
                 for col in columns {
-                    let name = col.name.to_string();
-                    /* TODO: Add datetime and timestamp here */
-                    let datatype = match &col.data_type {
-                        DataType::SmallInt => "int",
-                        DataType::Int => "int",
-                        DataType::BigInt => "int",
-                        DataType::Boolean => "bool",
-                        DataType::Text => "string",
-                        DataType::Varchar(_bytes) => "string",
-                        DataType::Float(_precision) => "float",
-                        DataType::Double => "float",
-                        DataType::Decimal(_precision1, _precision2) => "float",
-                        DataType::Custom(ObjectName(custom_type)) => {
-                            println!("custom type = {:?}", custom_type);
-                            match custom_type[0].as_ref() {
-                                "string" => "string",
-                                "tinyint" => "int",
-                                "tinyint(1)" => "int",
-                                "datetime" => "string",
-                                _ => "invalid",
-                            }
-                        }
-                        _ => {
-                            println!("not matched on custom type");
-                            "invalid"
-                        }
-                    };
+                    let name = col.name.value.to_string();
+                    let (datatype, mut is_auto_increment) = column_type(&col.data_type)?;
 
                     let mut is_pk: bool = false;
+                    let mut is_nullable: bool = !is_auto_increment;
+                    if is_auto_increment {
+                        let columns = vec![name.to_string()];
+                        indexes.push(ParsedIndex {
+                            name: index_name(&None, table_name, &columns),
+                            columns,
+                            is_unique: true,
+                        });
+                    }
+                    let mut default: Option<String> = None;
                     for column_option in &col.options {
-                        is_pk = match column_option.option {
-                            ColumnOption::Unique { is_primary } => is_primary,
-                            _ => false,
+                        match column_option.option {
+                            ColumnOption::Unique { is_primary: true } => is_pk = true,
+                            ColumnOption::Unique { is_primary: false } => {
+                                let columns = vec![name.to_string()];
+                                indexes.push(ParsedIndex {
+                                    name: index_name(&column_option.name, table_name, &columns),
+                                    columns,
+                                    is_unique: true,
+                                });
+                            }
+                            ColumnOption::ForeignKey {
+                                ref foreign_table,
+                                ref referred_columns,
+                                ref on_delete,
+                                ref on_update,
+                            } => {
+                                let name = match &column_option.name {
+                                    Some(name) => name.value.to_string(),
+                                    None => foreign_key_name(table_name, &foreign_keys),
+                                };
+                                foreign_keys.push(ParsedForeignKey {
+                                    name,
+                                    column: col.name.value.to_string(),
+                                    parent_table: foreign_table.to_string(),
+                                    parent_column: referred_column(referred_columns)?,
+                                    on_delete: ForeignKeyAction::new(on_delete)?,
+                                    on_update: ForeignKeyAction::new(on_update)?,
+                                });
+                            }
+                            ColumnOption::Check(ref expr) => {
+                                checks.push(parse_check(
+                                    &column_option.name,
+                                    expr,
+                                    table_name,
+                                    &checks,
+                                )?);
+                            }
+                            // MySQL's AUTO_INCREMENT and SQLite's AUTOINCREMENT.
+                            ColumnOption::DialectSpecific(ref tokens)
+                                if tokens.iter().any(|t| {
+                                    let t = t.to_string().to_uppercase();
+                                    t == "AUTO_INCREMENT" || t == "AUTOINCREMENT"
+                                }) =>
+                            {
+                                is_auto_increment = true
+                            }
+                            ColumnOption::NotNull => is_nullable = false,
+                            ColumnOption::Null => is_nullable = true,
+                            ColumnOption::Default(ref expr) => {
+                                default = default_value(expr, &name)?
+                            }
+                            _ => {}
                         };
                     }
 
                     parsed_columns.push(ParsedColumn {
                         name,
-                        datatype: datatype.to_string(),
+                        datatype,
                         is_pk,
-                        is_nullable: false,
+                        // A primary key can never be NULL.
+                        is_nullable: is_nullable && !is_pk,
+                        default,
+                        is_auto_increment,
                     });
                 }
-                //                TODO: Handle constraints,
-                //    Unique, Primary Key, Nullable, Default value etc.
-                for constraint in _constraints {
-                    println!("{:?}", constraint);
+                let mut primary_key = vec![];
+                for constraint in constraints {
+                    match constraint {
+                        TableConstraint::Unique {
+                            columns,
+                            is_primary: true,
+                            ..
+                        } => {
+                            let columns = column_names(&parsed_columns, columns)?;
+                            if !primary_key.is_empty() || parsed_columns.iter().any(|c| c.is_pk) {
+                                return Err("A table can only have one primary key".to_string());
+                            }
+                            for col in parsed_columns.iter_mut() {
+                                if columns.contains(&col.name) {
+                                    col.is_nullable = false;
+                                    // A single column key is kept on the column itself.
+                                    col.is_pk = columns.len() == 1;
+                                }
+                            }
+                            if columns.len() > 1 {
+                                primary_key = columns;
+                            }
+                        }
+                        TableConstraint::Unique {
+                            name,
+                            columns,
+                            is_primary: false,
+                        } => {
+                            let columns = column_names(&parsed_columns, columns)?;
+                            indexes.push(ParsedIndex {
+                                name: index_name(name, table_name, &columns),
+                                columns,
+                                is_unique: true,
+                            });
+                        }
+                        TableConstraint::Index { name, columns, .. } => {
+                            let columns = column_names(&parsed_columns, columns)?;
+                            indexes.push(ParsedIndex {
+                                name: index_name(name, table_name, &columns),
+                                columns,
+                                is_unique: false,
+                            });
+                        }
+                        TableConstraint::Check { name, expr } => {
+                            checks.push(parse_check(name, expr, table_name, &checks)?);
+                        }
+                        TableConstraint::ForeignKey {
+                            name,
+                            columns,
+                            foreign_table,
+                            referred_columns,
+                            on_delete,
+                            on_update,
+                        } => {
+                            let column = match column_names(&parsed_columns, columns)?.as_slice() {
+                                [column] => column.to_string(),
+                                _ => {
+                                    return Err(
+                                        "Foreign keys over several columns are not supported"
+                                            .to_string(),
+                                    )
+                                }
+                            };
+                            foreign_keys.push(ParsedForeignKey {
+                                name: match name {
+                                    Some(name) => name.value.to_string(),
+                                    None => foreign_key_name(table_name, &foreign_keys),
+                                },
+                                column,
+                                parent_table: foreign_table.to_string(),
+                                parent_column: referred_column(referred_columns)?,
+                                on_delete: ForeignKeyAction::new(on_delete)?,
+                                on_update: ForeignKeyAction::new(on_update)?,
+                            });
+                        }
+                        _ => return Err(format!("Constraint {} is not supported", constraint)),
+                    }
                 }
-                return Ok(CreateQuery {
+                // The primary key is unique already.
+                indexes.retain(|index| {
+                    !(index.is_unique
+                        && (index.columns == primary_key
+                            || parsed_columns
+                                .iter()
+                                .any(|c| c.is_pk && index.columns == [c.name.to_string()])))
+                });
+                Ok(CreateQuery {
                     table_name: table_name.to_string(),
                     columns: parsed_columns,
-                });
+                    primary_key,
+                    indexes,
+                    checks,
+                    foreign_keys,
+                    auto_increment_start: *auto_increment_offset,
+                    if_not_exists: *if_not_exists,
+                    query: query
+                        .as_ref()
+                        .map(|query| SelectQuery::new(&Statement::Query(query.clone())))
+                        .transpose()?,
+                })
             }
 
-            _ => return Err("Error parsing query".to_string()),
+            _ => Err("Error parsing query".to_string()),
         }
     }
 }
+
+/// The name the table gives to a column type, along with whether it is SERIAL, which also
+/// makes the column AUTO_INCREMENT.
+pub fn column_type(data_type: &DataType) -> Result<(String, bool), String> {
+    let mut is_serial = false;
+    let datatype = match data_type {
+        // MySQL spells BOOLEAN as TINYINT(1).
+        DataType::TinyInt(Some(1)) => "bool",
+        DataType::TinyInt(_) => "tinyint",
+        DataType::SmallInt(_) => "smallint",
+        DataType::MediumInt(_) => "int",
+        DataType::Int(_) => "int",
+        DataType::Integer(_) => "int",
+        DataType::BigInt(_) => "bigint",
+        DataType::Boolean => "bool",
+        DataType::Bool => "bool",
+        DataType::Text => "string",
+        DataType::String(_) => "string",
+        DataType::Char(_) => "string",
+        DataType::Varchar(_bytes) => "string",
+        DataType::Float(_precision) => "float",
+        DataType::Real => "float",
+        DataType::Double => "float",
+        DataType::Decimal(info) | DataType::Numeric(info) | DataType::Dec(info) => {
+            return Ok((decimal_type_name(info)?, false))
+        }
+        DataType::Date => "date",
+        DataType::Time(..) => "time",
+        DataType::Datetime(_) => "datetime",
+        DataType::Timestamp(..) => "timestamp",
+        DataType::Custom(ObjectName(custom_type), modifiers) => {
+            match custom_type[0].value.to_lowercase().as_ref() {
+                "string" => "string",
+                // Quoted type names, like `tinyint`, reach here instead of their own variant.
+                "tinyint" if modifiers == &["1"] => "bool",
+                "tinyint" => "tinyint",
+                "datetime" => "datetime",
+                // MySQL's BIGINT UNSIGNED NOT NULL AUTO_INCREMENT UNIQUE.
+                "serial" => {
+                    is_serial = true;
+                    "bigint"
+                }
+                _ => "invalid",
+            }
+        }
+        _ => "invalid",
+    };
+    Ok((datatype.to_string(), is_serial))
+}
+
+/// The value a `DEFAULT expr` gives the column `col_name`, `None` for NULL.
+pub fn default_value(expr: &Expr, col_name: &str) -> Result<Option<String>, String> {
+    match expr {
+        Expr::Value(Value::Null) => Ok(None),
+        _ => match literal_to_string(expr) {
+            Some(value) => Ok(Some(value)),
+            None => Err(format!(
+                "Unsupported default {} for column {}",
+                expr, col_name
+            )),
+        },
+    }
+}
+
+/// Spells out a DECIMAL type as `decimal(precision,scale)`, MySQL's `DECIMAL(10,0)` when
+/// they are left out.
+fn decimal_type_name(info: &ExactNumberInfo) -> Result<String, String> {
+    let (precision, scale) = match *info {
+        ExactNumberInfo::None => (10, 0),
+        ExactNumberInfo::Precision(precision) => (precision, 0),
+        ExactNumberInfo::PrecisionAndScale(precision, scale) => (precision, scale),
+    };
+    // Values are kept in an i128, which holds any 38 digit number.
+    if precision == 0 || precision > 38 || scale > precision {
+        return Err(format!("Unsupported type DECIMAL({},{})", precision, scale));
+    }
+    Ok(format!("decimal({},{})", precision, scale))
+}
+
+/// Parses a CHECK constraint, naming it `{table}_chk_{n}` the way MySQL does when it has no
+/// name.
+fn parse_check(
+    name: &Option<Ident>,
+    expr: &Expr,
+    table_name: &ObjectName,
+    checks: &[ParsedCheck],
+) -> Result<ParsedCheck, String> {
+    let name = match name {
+        Some(name) => name.value.to_string(),
+        None => format!("{}_chk_{}", table_name, checks.len() + 1),
+    };
+    let predicate = Predicate::new(expr)
+        .map_err(|err| format!("Unsupported check constraint {}: {}", name, err))?;
+    Ok(ParsedCheck {
+        name,
+        expr: expr.to_string(),
+        predicate,
+    })
+}
+
+/// Names a foreign key `{table}_ibfk_{n}` the way MySQL does.
+fn foreign_key_name(table_name: &ObjectName, foreign_keys: &[ParsedForeignKey]) -> String {
+    format!("{}_ibfk_{}", table_name, foreign_keys.len() + 1)
+}
+
+fn referred_column(referred_columns: &[Ident]) -> Result<Option<String>, String> {
+    match referred_columns {
+        [] => Ok(None),
+        [column] => Ok(Some(column.value.to_string())),
+        _ => Err("Foreign keys over several columns are not supported".to_string()),
+    }
+}
+
+/// The names of `columns`, all of which must be columns of the table being created.
+fn column_names(parsed_columns: &[ParsedColumn], columns: &[Ident]) -> Result<Vec<String>, String> {
+    columns
+        .iter()
+        .map(
+            |col| match parsed_columns.iter().any(|c| c.name == col.value) {
+                true => Ok(col.value.to_string()),
+                false => Err(format!("Cannot find column {} for key", col.value)),
+            },
+        )
+        .collect()
+}
+
+/// The name of an index, made up from its table and columns when it has none.
+pub fn index_name(name: &Option<Ident>, table_name: &ObjectName, columns: &[String]) -> String {
+    match name {
+        Some(name) => name.value.to_string(),
+        None => format!("{}_{}_idx", table_name, columns.join("_")),
+    }
+}
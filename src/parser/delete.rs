@@ -0,0 +1,38 @@
+use sqlparser::ast::{Statement, TableFactor};
+
+use crate::parser::select::Predicate;
+
+#[derive(Debug)]
+pub struct DeleteQuery {
+    pub table_name: String,
+    pub where_clause: Option<Predicate>,
+}
+
+impl DeleteQuery {
+    pub fn new(statement: &Statement) -> Result<DeleteQuery, String> {
+        match statement {
+            Statement::Delete {
+                from, selection, ..
+            } => {
+                let table_name = match from.as_slice() {
+                    [table] => match &table.relation {
+                        TableFactor::Table { name, .. } => name.to_string(),
+                        _ => return Err("Can only delete from a single table".to_string()),
+                    },
+                    _ => return Err("Can only delete from a single table".to_string()),
+                };
+
+                let where_clause = match selection {
+                    Some(where_expression) => Some(Predicate::new(where_expression)?),
+                    None => None,
+                };
+
+                Ok(DeleteQuery {
+                    table_name,
+                    where_clause,
+                })
+            }
+            _ => Err("Error parsing delete query".to_string()),
+        }
+    }
+}
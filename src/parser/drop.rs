@@ -0,0 +1,45 @@
+use sqlparser::ast::{ObjectType, Statement};
+
+#[derive(Debug)]
+pub struct DropTableQuery {
+    pub names: Vec<String>,
+    pub if_exists: bool,
+}
+
+impl DropTableQuery {
+    pub fn new(statement: &Statement) -> Result<DropTableQuery, String> {
+        match statement {
+            Statement::Drop {
+                object_type: ObjectType::Table,
+                if_exists,
+                names,
+                ..
+            } => Ok(DropTableQuery {
+                names: names.iter().map(|name| name.to_string()).collect(),
+                if_exists: *if_exists,
+            }),
+            _ => Err("Error parsing drop table query".to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TruncateQuery {
+    pub table_name: String,
+}
+
+impl TruncateQuery {
+    pub fn new(statement: &Statement) -> Result<TruncateQuery, String> {
+        match statement {
+            Statement::Truncate {
+                table_name,
+                partitions: None,
+                ..
+            } => Ok(TruncateQuery {
+                table_name: table_name.to_string(),
+            }),
+            Statement::Truncate { .. } => Err("Cannot truncate partitions".to_string()),
+            _ => Err("Error parsing truncate query".to_string()),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use sqlparser::ast::{Expr, ObjectType, Statement};
+
+use crate::parser::create::index_name;
+
+#[derive(Debug)]
+pub struct CreateIndexQuery {
+    pub name: String,
+    pub table_name: String,
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+    pub if_not_exists: bool,
+}
+
+impl CreateIndexQuery {
+    pub fn new(statement: &Statement) -> Result<CreateIndexQuery, String> {
+        match statement {
+            Statement::CreateIndex {
+                name,
+                table_name,
+                columns,
+                unique,
+                if_not_exists,
+                predicate,
+                ..
+            } => {
+                if predicate.is_some() {
+                    return Err("Partial indexes are not supported".to_string());
+                }
+                let mut column_names = vec![];
+                for column in columns {
+                    match &column.expr {
+                        Expr::Identifier(col_name) => column_names.push(col_name.value.to_string()),
+                        expr => return Err(format!("Cannot index expression {}", expr)),
+                    }
+                }
+                let name = match name {
+                    Some(name) => name.to_string(),
+                    None => index_name(&None, table_name, &column_names),
+                };
+
+                Ok(CreateIndexQuery {
+                    name,
+                    table_name: table_name.to_string(),
+                    columns: column_names,
+                    is_unique: *unique,
+                    if_not_exists: *if_not_exists,
+                })
+            }
+            _ => Err("Error parsing create index query".to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DropIndexQuery {
+    pub names: Vec<String>,
+    pub if_exists: bool,
+}
+
+impl DropIndexQuery {
+    pub fn new(statement: &Statement) -> Result<DropIndexQuery, String> {
+        match statement {
+            Statement::Drop {
+                object_type: ObjectType::Index,
+                if_exists,
+                names,
+                ..
+            } => Ok(DropIndexQuery {
+                names: names.iter().map(|name| name.to_string()).collect(),
+                if_exists: *if_exists,
+            }),
+            _ => Err("Error parsing drop index query".to_string()),
+        }
+    }
+}
@@ -1,69 +1,63 @@
-use sqlparser::ast::{Expr, Query, SetExpr, Statement, Value, Values};
+use sqlparser::ast::{Expr, FunctionArg, FunctionArgExpr, SetExpr, Statement, Value, Values};
+
+use crate::parser::select::{literal_to_string, SelectQuery};
+
+#[derive(Debug, PartialEq)]
+pub enum InsertValue {
+    Value(Option<String>), // None stands for NULL
+    /// `nextval('name')`, the next value of the sequence `name`.
+    NextVal(String),
+}
 
 pub struct InsertQuery {
     pub table_name: String,
     pub columns: Vec<String>,
-    pub values: Vec<Vec<String>>,
+    pub values: Vec<Vec<InsertValue>>,
+    pub select: Option<SelectQuery>, // INSERT ... SELECT, whose rows are inserted instead
 }
 
 impl InsertQuery {
     pub fn new(statement: &Statement) -> Result<InsertQuery, String> {
         let mut tname: Option<String> = None;
         let mut columns: Vec<String> = vec![];
-        let mut all_vals: Vec<Vec<String>> = vec![];
+        let mut all_vals: Vec<Vec<InsertValue>> = vec![];
+        let mut select = None;
 
         if let Statement::Insert {
             table_name,
             columns: cols,
-            source,
+            source: Some(source),
+            ..
         } = statement
         {
             tname = Some(table_name.to_string());
             for c in cols {
-                columns.push(c.to_string());
+                columns.push(c.value.to_string());
             }
-            match &**source {
-                Query {
-                    ctes: _ctes,
-                    body,
-                    order_by: _order_by,
-                    limit: _limit,
-                    offset: _offset,
-                    fetch: _fetch,
-                } => {
-                    if let SetExpr::Values(values) = body {
-                        if let Values(expressions) = values {
-                            for i in expressions {
-                                let mut value_set: Vec<String> = vec![];
-                                for e in i {
-                                    match e {
-                                        Expr::Value(v) => match v {
-                                            Value::Number(n) => {
-                                                value_set.push(n.to_string());
-                                            }
-                                            Value::Boolean(b) => match *b {
-                                                true => value_set.push("true".to_string()),
-                                                false => value_set.push("false".to_string()),
-                                            },
-                                            Value::SingleQuotedString(sqs) => {
-                                                value_set.push(sqs.to_string());
-                                            }
-                                            Value::Null => {
-                                                value_set.push("Null".to_string());
-                                            }
-                                            _ => {}
-                                        },
-                                        Expr::Identifier(i) => {
-                                            value_set.push(i.to_string());
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                                all_vals.push(value_set);
+            if let SetExpr::Values(Values { rows, .. }) = &*source.body {
+                for i in rows {
+                    let mut value_set: Vec<InsertValue> = vec![];
+                    for e in i {
+                        match e {
+                            Expr::Value(Value::Null) => value_set.push(InsertValue::Value(None)),
+                            Expr::Identifier(i) => {
+                                value_set.push(InsertValue::Value(Some(i.value.to_string())));
+                            }
+                            Expr::Function(f) if f.name.to_string().to_lowercase() == "nextval" => {
+                                value_set.push(InsertValue::NextVal(sequence_name(e, &f.args)?));
                             }
+                            _ => match literal_to_string(e) {
+                                Some(v) => value_set.push(InsertValue::Value(Some(v))),
+                                None => {
+                                    return Err(format!("Cannot insert {}, expected a value", e))
+                                }
+                            },
                         }
                     }
+                    all_vals.push(value_set);
                 }
+            } else {
+                select = Some(SelectQuery::new(&Statement::Query(source.clone()))?);
             }
         }
 
@@ -72,8 +66,22 @@ impl InsertQuery {
                 table_name: t,
                 columns,
                 values: all_vals,
+                select,
             }),
             None => Err(String::from("Cannot parse insert query")),
         }
     }
 }
+
+/// The sequence named by the single string argument of `nextval`.
+fn sequence_name(call: &Expr, args: &[FunctionArg]) -> Result<String, String> {
+    match args {
+        [FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Value(Value::SingleQuotedString(
+            name,
+        ))))] => Ok(name.to_string()),
+        _ => Err(format!(
+            "Cannot insert {}, expected nextval('sequence')",
+            call
+        )),
+    }
+}
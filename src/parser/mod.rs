@@ -1,3 +1,9 @@
+pub mod alter;
 pub mod create;
+pub mod delete;
+pub mod drop;
+pub mod index;
 pub mod insert;
 pub mod select;
+pub mod sequence;
+pub mod update;
@@ -1,17 +1,49 @@
+use serde::{Deserialize, Serialize};
 use sqlparser::ast::{
-    Expr,
+    BinaryOperator, Expr, Function, FunctionArg, FunctionArgExpr, GroupByExpr, JoinConstraint,
+    JoinOperator, OrderByExpr,
     SelectItem::{ExprWithAlias, QualifiedWildcard, UnnamedExpr, Wildcard},
-    SetExpr, Statement, TableFactor, Value,
+    SetExpr, Statement, TableFactor, UnaryOperator, Value,
 };
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum Binary {
     Eq,
+    NotEq,
     Lt,
+    LtEq,
     Gt,
+    GtEq,
 }
 
-#[derive(Debug)]
+impl Binary {
+    pub fn new(op: &BinaryOperator) -> Result<Binary, String> {
+        match op {
+            BinaryOperator::Eq => Ok(Binary::Eq),
+            BinaryOperator::NotEq => Ok(Binary::NotEq),
+            BinaryOperator::Lt => Ok(Binary::Lt),
+            BinaryOperator::LtEq => Ok(Binary::LtEq),
+            BinaryOperator::Gt => Ok(Binary::Gt),
+            BinaryOperator::GtEq => Ok(Binary::GtEq),
+            _ => Err(format!("Unsupported operator {} in where clause", op)),
+        }
+    }
+
+    /// The operator holding exactly where this one doesn't, NULL aside.
+    pub fn negate(&self) -> Binary {
+        match self {
+            Binary::Eq => Binary::NotEq,
+            Binary::NotEq => Binary::Eq,
+            Binary::Lt => Binary::GtEq,
+            Binary::LtEq => Binary::Gt,
+            Binary::Gt => Binary::LtEq,
+            Binary::GtEq => Binary::Lt,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum Operator {
     // Unary,
     Binary(Binary),
@@ -20,223 +52,505 @@ pub enum Operator {
 }
 
 // Only binary operators for now
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Expression {
     pub left: String,
     pub right: String,
     pub op: Operator,
+    /// Set when the left side is an aggregate, as in `HAVING COUNT(*) > 2`. `left` then holds
+    /// its rendered form.
+    pub aggregate: Option<Aggregate>,
+}
+
+impl Expression {
+    pub fn new(left: &Expr, op: &BinaryOperator, right: &Expr) -> Result<Expression, String> {
+        let (col_name, aggregate) = match left {
+            Expr::Identifier(_) | Expr::CompoundIdentifier(_) => (column_name(left).unwrap(), None),
+            Expr::Function(function) => {
+                let aggregate = Aggregate::new(function)?;
+                (aggregate.to_string(), Some(aggregate))
+            }
+            _ => {
+                return Err(format!(
+                    "Left side of {} {} {} must be a column name",
+                    left, op, right
+                ))
+            }
+        };
+
+        // A column on the right, as in `a = b`, would otherwise be taken for the text "b".
+        let value = match literal_to_string(right) {
+            Some(v) => v,
+            None => {
+                return Err(format!(
+                    "Right side of {} {} {} must be a value",
+                    left, op, right
+                ))
+            }
+        };
+
+        Ok(Expression {
+            left: col_name,
+            right: value,
+            op: Operator::Binary(Binary::new(op)?),
+            aggregate,
+        })
+    }
+}
+
+/// Boolean filter built from a WHERE clause. Leaves are single column comparisons and NULL
+/// checks, inner nodes combine them the way the SQL expression did, parentheses included.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum Predicate {
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+    Expression(Expression),
+    IsNull(String),
+    IsNotNull(String),
+}
+
+impl Predicate {
+    pub fn new(expr: &Expr) -> Result<Predicate, String> {
+        match expr {
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => Ok(Predicate::And(
+                Box::new(Predicate::new(left)?),
+                Box::new(Predicate::new(right)?),
+            )),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::Or,
+                right,
+            } => Ok(Predicate::Or(
+                Box::new(Predicate::new(left)?),
+                Box::new(Predicate::new(right)?),
+            )),
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => Ok(Predicate::Not(Box::new(Predicate::new(expr)?))),
+            Expr::Nested(expr) => Predicate::new(expr),
+            Expr::BinaryOp { left, op, right } => {
+                Ok(Predicate::Expression(Expression::new(left, op, right)?))
+            }
+            Expr::IsNull(inner) | Expr::IsNotNull(inner) => {
+                let col_name = match column_name(inner) {
+                    Some(col_name) => col_name,
+                    None => return Err(format!("{} must check a column", expr)),
+                };
+                match expr {
+                    Expr::IsNull(_) => Ok(Predicate::IsNull(col_name)),
+                    _ => Ok(Predicate::IsNotNull(col_name)),
+                }
+            }
+            _ => Err(format!("Cannot parse where clause expression {}", expr)),
+        }
+    }
+
+    /// The predicate holding exactly where this one is false. A comparison with NULL is
+    /// neither true nor false, so NOT is pushed down to the leaves rather than taken as the
+    /// complement of the matched rows.
+    pub fn negate(&self) -> Predicate {
+        match self {
+            Predicate::And(left, right) => {
+                Predicate::Or(Box::new(left.negate()), Box::new(right.negate()))
+            }
+            Predicate::Or(left, right) => {
+                Predicate::And(Box::new(left.negate()), Box::new(right.negate()))
+            }
+            Predicate::Not(inner) => *inner.clone(),
+            Predicate::Expression(expr) => {
+                let Operator::Binary(op) = &expr.op;
+                Predicate::Expression(Expression {
+                    op: Operator::Binary(op.negate()),
+                    ..expr.clone()
+                })
+            }
+            Predicate::IsNull(col_name) => Predicate::IsNotNull(col_name.to_string()),
+            Predicate::IsNotNull(col_name) => Predicate::IsNull(col_name.to_string()),
+        }
+    }
+}
+
+/// Name of the column `expr` refers to, qualified names such as `a.col` are kept as written.
+pub fn column_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Identifier(col_name) => Some(col_name.value.to_string()),
+        Expr::CompoundIdentifier(idents) => Some(
+            idents
+                .iter()
+                .map(|i| i.value.to_string())
+                .collect::<Vec<String>>()
+                .join("."),
+        ),
+        _ => None,
+    }
+}
+
+/// Renders a literal the way it is stored in the columns, `None` if `expr` isn't a literal.
+pub fn literal_to_string(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Value(v) => match v {
+            Value::Number(n, _) => Some(n.to_string()),
+            Value::SingleQuotedString(s) => Some(s.to_string()),
+            Value::NationalStringLiteral(s) => Some(s.to_string()),
+            Value::Boolean(b) => Some(b.to_string()),
+            _ => None,
+        },
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => literal_to_string(expr).map(|v| format!("-{}", v)),
+        _ => None,
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AggregateFunction::Count => f.write_str("COUNT"),
+            AggregateFunction::Sum => f.write_str("SUM"),
+            AggregateFunction::Avg => f.write_str("AVG"),
+            AggregateFunction::Min => f.write_str("MIN"),
+            AggregateFunction::Max => f.write_str("MAX"),
+        }
+    }
+}
+
+/// An aggregate call in the projection, `column` is `None` for `COUNT(*)`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Aggregate {
+    pub function: AggregateFunction,
+    pub column: Option<String>,
+}
+
+impl Aggregate {
+    pub fn new(function: &Function) -> Result<Aggregate, String> {
+        let aggregate_function = match function.name.to_string().to_uppercase().as_ref() {
+            "COUNT" => AggregateFunction::Count,
+            "SUM" => AggregateFunction::Sum,
+            "AVG" => AggregateFunction::Avg,
+            "MIN" => AggregateFunction::Min,
+            "MAX" => AggregateFunction::Max,
+            _ => return Err(format!("Unsupported function {}", function)),
+        };
+        if function.distinct || function.filter.is_some() || function.over.is_some() {
+            return Err(format!("Unsupported aggregate {}", function));
+        }
+
+        let column = match function.args.as_slice() {
+            [FunctionArg::Unnamed(FunctionArgExpr::Wildcard)]
+                if aggregate_function == AggregateFunction::Count =>
+            {
+                None
+            }
+            [FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))] if column_name(expr).is_some() => {
+                column_name(expr)
+            }
+            _ => {
+                return Err(format!(
+                    "{} expects a single column name, found {}",
+                    aggregate_function, function
+                ))
+            }
+        };
+
+        Ok(Aggregate {
+            function: aggregate_function,
+            column,
+        })
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.column {
+            Some(column) => write!(f, "{}({})", self.function, column),
+            None => write!(f, "{}(*)", self.function),
+        }
+    }
+}
+
+/// One item of the select list. A plain `*` is kept as the column `*` until the table it
+/// expands to is known.
+#[derive(Debug, PartialEq)]
+pub enum Projection {
+    Column(String),
+    Aggregate(Aggregate),
+}
+
+impl Projection {
+    fn new(expr: &Expr) -> Result<Projection, String> {
+        match expr {
+            Expr::Identifier(_) | Expr::CompoundIdentifier(_) => {
+                Ok(Projection::Column(column_name(expr).unwrap()))
+            }
+            Expr::Function(function) => Ok(Projection::Aggregate(Aggregate::new(function)?)),
+            _ => Err(format!(
+                "Cannot select {}, expected a column or an aggregate",
+                expr
+            )),
+        }
+    }
+
+    /// The table column this item reads, if any.
+    pub fn column(&self) -> Option<&String> {
+        match self {
+            Projection::Column(column) => Some(column),
+            Projection::Aggregate(aggregate) => aggregate.column.as_ref(),
+        }
+    }
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Projection::Column(column) => f.write_str(column),
+            Projection::Aggregate(aggregate) => write!(f, "{}", aggregate),
+        }
+    }
+}
+
+/// One key of an ORDER BY clause.
+#[derive(Debug)]
+pub struct OrderBy {
+    pub column: String,
+    pub asc: bool,
+    pub nulls_first: bool,
+}
+
+impl OrderBy {
+    pub fn new(order_by_expr: &OrderByExpr) -> Result<OrderBy, String> {
+        let column = match column_name(&order_by_expr.expr) {
+            Some(column) => column,
+            None => {
+                return Err(format!(
+                    "Cannot order by {}, expected a column name",
+                    order_by_expr.expr
+                ))
+            }
+        };
+        let asc = order_by_expr.asc.unwrap_or(true);
+
+        Ok(OrderBy {
+            column,
+            asc,
+            // Like MySQL, NULL sorts below every other value unless told otherwise.
+            nulls_first: order_by_expr.nulls_first.unwrap_or(asc),
+        })
+    }
+}
+
+/// Reads the row count given to LIMIT or OFFSET.
+fn parse_row_count(expr: &Expr, clause: &str) -> Result<usize, String> {
+    match literal_to_string(expr).map(|v| v.parse::<usize>()) {
+        Some(Ok(count)) => Ok(count),
+        _ => Err(format!(
+            "{} expects a non-negative integer, found {}",
+            clause, expr
+        )),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+/// One `JOIN table [alias] ON left = right` of the from clause. Only equality between two
+/// columns is supported as the join condition.
+#[derive(Debug)]
+pub struct Join {
+    pub kind: JoinKind,
+    pub table: String,
+    pub alias: Option<String>,
+    pub left: String,
+    pub right: String,
+}
+
+impl Join {
+    pub fn new(join: &sqlparser::ast::Join) -> Result<Join, String> {
+        let (table, alias) = match &join.relation {
+            TableFactor::Table { name, alias, .. } => (
+                name.to_string(),
+                alias.as_ref().map(|a| a.name.value.to_string()),
+            ),
+            relation => return Err(format!("Cannot join {}, expected a table", relation)),
+        };
+        let (kind, constraint) = match &join.join_operator {
+            JoinOperator::Inner(constraint) => (JoinKind::Inner, constraint),
+            JoinOperator::LeftOuter(constraint) => (JoinKind::Left, constraint),
+            JoinOperator::RightOuter(constraint) => (JoinKind::Right, constraint),
+            JoinOperator::FullOuter(constraint) => (JoinKind::Full, constraint),
+            _ => return Err(format!("Unsupported join {}", join)),
+        };
+        let (left, right) = match constraint {
+            JoinConstraint::On(Expr::BinaryOp {
+                left,
+                op: BinaryOperator::Eq,
+                right,
+            }) => match (column_name(left), column_name(right)) {
+                (Some(left), Some(right)) => (left, right),
+                _ => {
+                    return Err(format!(
+                        "Join condition of {} must compare two columns",
+                        join
+                    ))
+                }
+            },
+            _ => return Err(format!("{} needs an ON a.col = b.col condition", join)),
+        };
+
+        Ok(Join {
+            kind,
+            table,
+            alias,
+            left,
+            right,
+        })
+    }
 }
 
 #[derive(Debug)]
 pub struct SelectQuery {
-    pub from: String,            // table name
-    pub projection: Vec<String>, // columns that will be fetched
-    pub where_expressions: Vec<Expression>,
+    pub from: String, // table name
+    pub from_alias: Option<String>,
+    pub joins: Vec<Join>,
+    pub projection: Vec<Projection>, // columns and aggregates that will be fetched
+    pub where_clause: Option<Predicate>,
+    pub group_by: Vec<String>,
+    pub having: Option<Predicate>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<usize>,
+    pub offset: usize,
 }
 
 impl SelectQuery {
     pub fn new(statement: &Statement) -> Result<SelectQuery, String> {
         let mut table_name: Option<String> = None;
-        let mut projection: Vec<String> = vec![];
-        let mut where_expressions: Vec<Expression> = vec![];
+        let mut from_alias: Option<String> = None;
+        let mut joins: Vec<Join> = vec![];
+        let mut projection: Vec<Projection> = vec![];
+        let mut where_clause: Option<Predicate> = None;
+        let mut group_by: Vec<String> = vec![];
+        let mut having: Option<Predicate> = None;
+        let mut order_by: Vec<OrderBy> = vec![];
+        let mut limit: Option<usize> = None;
+        let mut offset: usize = 0;
 
         match statement {
-            Statement::Query(bq) => match &(*bq).body {
+            Statement::Query(bq) => match &*bq.body {
                 SetExpr::Select(select) => {
-                    for p in &(*select).projection {
+                    for p in &select.projection {
                         match p {
-                            UnnamedExpr(exp) => match exp {
-                                Expr::Identifier(i) => {
-                                    projection.push(i.to_string());
-                                }
-                                _ => {
-                                    println!(
-                                        "Failing to parse expression in the where clause.\
-                                         It's probably not an identifier or a value.\
-                                         Cannot parse nested expressions :( ."
-                                    );
-                                }
-                            },
-                            QualifiedWildcard(obj_name) => {
-                                println!("Found qualified wildcard in the expression. Wildcard name is  {}", obj_name);
+                            UnnamedExpr(exp) => projection.push(Projection::new(exp)?),
+                            QualifiedWildcard(obj_name, _) => {
+                                return Err(format!(
+                                    "{}.* is not supported, use * or name the columns",
+                                    obj_name
+                                ))
                             }
-                            Wildcard => {
-                                projection.push("*".to_string());
+                            Wildcard(_) => {
+                                projection.push(Projection::Column("*".to_string()));
+                            }
+                            ExprWithAlias { expr, alias: _ } => {
+                                projection.push(Projection::new(expr)?)
                             }
-                            ExprWithAlias { expr, alias } => match expr {
-                                Expr::Identifier(i) => {
-                                    projection.push(i.to_string());
-                                }
-                                _ => {
-                                    println!("Detected expression with alias. Cannot parse expression with alias.");
-                                }
-                            },
                         }
                     }
 
-                    for f in &(*select).from {
+                    if select.from.len() > 1 {
+                        return Err(
+                            "Cannot select from several tables separated by commas, use JOIN"
+                                .to_string(),
+                        );
+                    }
+                    for f in &select.from {
                         match &f.relation {
-                            TableFactor::Table {
-                                name,
-                                alias,
-                                args: _args,
-                                with_hints: _with_hints,
-                            } => {
+                            TableFactor::Table { name, alias, .. } => {
                                 table_name = Some(name.to_string());
-                                match alias {
-                                    Some(alias) => println!("alias = {}", alias),
-                                    None => println!("No table alias"),
-                                }
+                                from_alias = alias.as_ref().map(|a| a.name.value.to_string());
                             }
-                            _ => println!("Nested join or derived tables"),
+                            _ => {
+                                return Err(
+                                    "Cannot select from nested joins or derived tables".to_string()
+                                )
+                            }
+                        }
+                        for join in &f.joins {
+                            joins.push(Join::new(join)?);
                         }
                     }
 
-                    match &(*select).selection {
-                        Some(where_expression) => {
-                            println!("where expression in select.rs = {:?}", where_expression);
-                            match where_expression {
-                                Expr::BinaryOp { left, op, right } => {
-                                    if let Expr::Identifier(col_name) = &(**left) {
-                                        if let Expr::Value(v) = &(**right) {
-                                            if let Value::Number(n) = v {
-                                                match op {
-                                                    sqlparser::ast::BinaryOperator::Eq => {
-                                                        where_expressions.push(Expression {
-                                                            left: col_name.to_string(),
-                                                            right: n.to_string(),
-                                                            op: Operator::Binary(Binary::Eq),
-                                                        });
-                                                    }
-                                                    sqlparser::ast::BinaryOperator::Gt => {
-                                                        where_expressions.push(Expression {
-                                                            left: col_name.to_string(),
-                                                            right: n.to_string(),
-                                                            op: Operator::Binary(Binary::Gt),
-                                                        });
-                                                    }
-                                                    sqlparser::ast::BinaryOperator::Lt => {
-                                                        where_expressions.push(Expression {
-                                                            left: col_name.to_string(),
-                                                            right: n.to_string(),
-                                                            op: Operator::Binary(Binary::Lt),
-                                                        });
-                                                    }
-                                                    _ => {
-                                                        panic!("cannot parse select query");
-                                                    }
-                                                };
-                                            }
-
-                                            if let Value::NationalStringLiteral(n) = v {
-                                                match op {
-                                                    sqlparser::ast::BinaryOperator::Eq => {
-                                                        where_expressions.push(Expression {
-                                                            left: col_name.to_string(),
-                                                            right: n.to_string(),
-                                                            op: Operator::Binary(Binary::Eq),
-                                                        });
-                                                    }
-                                                    sqlparser::ast::BinaryOperator::Gt => {
-                                                        where_expressions.push(Expression {
-                                                            left: col_name.to_string(),
-                                                            right: n.to_string(),
-                                                            op: Operator::Binary(Binary::Gt),
-                                                        });
-                                                    }
-                                                    sqlparser::ast::BinaryOperator::Lt => {
-                                                        where_expressions.push(Expression {
-                                                            left: col_name.to_string(),
-                                                            right: n.to_string(),
-                                                            op: Operator::Binary(Binary::Lt),
-                                                        });
-                                                    }
-                                                    _ => {
-                                                        panic!("cannot parse select query");
-                                                    }
-                                                };
-                                            }
-
-                                            if let Value::SingleQuotedString(n) = v {
-                                                match op {
-                                                    sqlparser::ast::BinaryOperator::Eq => {
-                                                        where_expressions.push(Expression {
-                                                            left: col_name.to_string(),
-                                                            right: n.to_string(),
-                                                            op: Operator::Binary(Binary::Eq),
-                                                        });
-                                                    }
-                                                    sqlparser::ast::BinaryOperator::Gt => {
-                                                        where_expressions.push(Expression {
-                                                            left: col_name.to_string(),
-                                                            right: n.to_string(),
-                                                            op: Operator::Binary(Binary::Gt),
-                                                        });
-                                                    }
-                                                    sqlparser::ast::BinaryOperator::Lt => {
-                                                        where_expressions.push(Expression {
-                                                            left: col_name.to_string(),
-                                                            right: n.to_string(),
-                                                            op: Operator::Binary(Binary::Lt),
-                                                        });
-                                                    }
-                                                    _ => {
-                                                        panic!("cannot parse select query");
-                                                    }
-                                                };
-                                            }
-                                        }
-
-                                        if let Expr::Identifier(v) = &(**right) {
-                                            match op {
-                                                sqlparser::ast::BinaryOperator::Eq => {
-                                                    where_expressions.push(Expression {
-                                                        left: col_name.to_string(),
-                                                        right: v.to_string(),
-                                                        op: Operator::Binary(Binary::Eq),
-                                                    });
-                                                }
-                                                sqlparser::ast::BinaryOperator::Gt => {
-                                                    where_expressions.push(Expression {
-                                                        left: col_name.to_string(),
-                                                        right: v.to_string(),
-                                                        op: Operator::Binary(Binary::Gt),
-                                                    });
-                                                }
-                                                sqlparser::ast::BinaryOperator::Lt => {
-                                                    where_expressions.push(Expression {
-                                                        left: col_name.to_string(),
-                                                        right: v.to_string(),
-                                                        op: Operator::Binary(Binary::Lt),
-                                                    });
-                                                }
-                                                _ => {
-                                                    panic!("cannot parse select query");
-                                                }
-                                            };
-                                        }
-                                    };
+                    if let Some(where_expression) = &select.selection {
+                        where_clause = Some(Predicate::new(where_expression)?);
+                    }
+
+                    match &select.group_by {
+                        GroupByExpr::Expressions(exprs) => {
+                            for expr in exprs {
+                                match column_name(expr) {
+                                    Some(col_name) => group_by.push(col_name),
+                                    None => {
+                                        return Err(format!(
+                                            "Cannot group by {}, expected a column name",
+                                            expr
+                                        ))
+                                    }
                                 }
-                                _ => {}
                             }
                         }
-                        None => {}
+                        GroupByExpr::All => return Err("GROUP BY ALL is not supported".to_string()),
+                    }
+
+                    if let Some(having_expression) = &select.having {
+                        having = Some(Predicate::new(having_expression)?);
+                    }
+
+                    for order_by_expr in &bq.order_by {
+                        order_by.push(OrderBy::new(order_by_expr)?);
+                    }
+
+                    if let Some(limit_expr) = &bq.limit {
+                        limit = Some(parse_row_count(limit_expr, "LIMIT")?);
+                    }
+                    if let Some(offset_expr) = &bq.offset {
+                        offset = parse_row_count(&offset_expr.value, "OFFSET")?;
                     }
                 }
-                _ => {
-                    println!("don't care");
-                }
+                _ => return Err("Only plain SELECT queries are supported".to_string()),
             },
-            _ => {
-                println!("don't care");
-            }
+            _ => return Err("Not a select statement".to_string()),
         }
 
         match table_name {
             Some(name) => Ok(SelectQuery {
                 from: name,
+                from_alias,
+                joins,
                 projection,
-                where_expressions,
+                where_clause,
+                group_by,
+                having,
+                order_by,
+                limit,
+                offset,
             }),
             None => Err(
                 "Error while trying to parse select statement. Cannot extract table name"
@@ -245,8 +559,8 @@ impl SelectQuery {
         }
     }
 
-    pub fn insert_projections(&mut self, projection: Vec<String>) -> &mut SelectQuery {
+    pub fn insert_projections(&mut self, projection: Vec<Projection>) -> &mut SelectQuery {
         self.projection = projection;
-        return self;
+        self
     }
 }
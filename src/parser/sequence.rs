@@ -0,0 +1,82 @@
+use sqlparser::ast::{Expr, MinMaxValue, ObjectType, SequenceOptions, Statement};
+
+use crate::parser::select::literal_to_string;
+
+#[derive(Debug)]
+pub struct CreateSequenceQuery {
+    pub name: String,
+    pub start: i64,
+    pub increment: i64,
+    pub if_not_exists: bool,
+}
+
+impl CreateSequenceQuery {
+    pub fn new(statement: &Statement) -> Result<CreateSequenceQuery, String> {
+        match statement {
+            Statement::CreateSequence {
+                name,
+                if_not_exists,
+                sequence_options,
+                ..
+            } => {
+                let mut start = None;
+                let mut increment = 1;
+                for option in sequence_options {
+                    match option {
+                        SequenceOptions::StartWith(expr, _) => start = Some(parse_number(expr)?),
+                        SequenceOptions::IncrementBy(expr, _) => increment = parse_number(expr)?,
+                        SequenceOptions::MinValue(MinMaxValue::Empty | MinMaxValue::None)
+                        | SequenceOptions::MaxValue(MinMaxValue::Empty | MinMaxValue::None) => {}
+                        option => {
+                            return Err(format!(
+                                "Unsupported sequence option {}",
+                                option.to_string().trim()
+                            ));
+                        }
+                    }
+                }
+                if increment == 0 {
+                    return Err(format!("Sequence {} cannot increment by 0", name));
+                }
+
+                Ok(CreateSequenceQuery {
+                    name: name.to_string(),
+                    // Like in PostgreSQL, a descending sequence starts from -1.
+                    start: start.unwrap_or(increment.signum()),
+                    increment,
+                    if_not_exists: *if_not_exists,
+                })
+            }
+            _ => Err("Error parsing create sequence query".to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DropSequenceQuery {
+    pub names: Vec<String>,
+    pub if_exists: bool,
+}
+
+impl DropSequenceQuery {
+    pub fn new(statement: &Statement) -> Result<DropSequenceQuery, String> {
+        match statement {
+            Statement::Drop {
+                object_type: ObjectType::Sequence,
+                if_exists,
+                names,
+                ..
+            } => Ok(DropSequenceQuery {
+                names: names.iter().map(|name| name.to_string()).collect(),
+                if_exists: *if_exists,
+            }),
+            _ => Err("Error parsing drop sequence query".to_string()),
+        }
+    }
+}
+
+fn parse_number(expr: &Expr) -> Result<i64, String> {
+    literal_to_string(expr)
+        .and_then(|val| val.parse::<i64>().ok())
+        .ok_or_else(|| format!("Expected an integer, found {}", expr))
+}
@@ -0,0 +1,128 @@
+use sqlparser::ast::{BinaryOperator, Expr, Statement, TableFactor, Value};
+use std::fmt;
+
+use crate::parser::select::{literal_to_string, Predicate};
+
+#[derive(Debug, PartialEq)]
+pub enum Arithmetic {
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl Arithmetic {
+    pub fn new(op: &BinaryOperator) -> Result<Arithmetic, String> {
+        match op {
+            BinaryOperator::Plus => Ok(Arithmetic::Plus),
+            BinaryOperator::Minus => Ok(Arithmetic::Minus),
+            BinaryOperator::Multiply => Ok(Arithmetic::Multiply),
+            BinaryOperator::Divide => Ok(Arithmetic::Divide),
+            BinaryOperator::Modulo => Ok(Arithmetic::Modulo),
+            _ => Err(format!("Unsupported operator {} in expression", op)),
+        }
+    }
+}
+
+impl fmt::Display for Arithmetic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Arithmetic::Plus => f.write_str("+"),
+            Arithmetic::Minus => f.write_str("-"),
+            Arithmetic::Multiply => f.write_str("*"),
+            Arithmetic::Divide => f.write_str("/"),
+            Arithmetic::Modulo => f.write_str("%"),
+        }
+    }
+}
+
+/// A value computed for every row, e.g. the right hand side of `SET price = price * 2`.
+#[derive(Debug)]
+pub enum Scalar {
+    Column(String),
+    Literal(String),
+    Null,
+    Arithmetic(Box<Scalar>, Arithmetic, Box<Scalar>),
+}
+
+impl Scalar {
+    pub fn new(expr: &Expr) -> Result<Scalar, String> {
+        match expr {
+            Expr::Identifier(col_name) => Ok(Scalar::Column(col_name.value.to_string())),
+            Expr::Nested(expr) => Scalar::new(expr),
+            Expr::Value(Value::Null) => Ok(Scalar::Null),
+            Expr::BinaryOp { left, op, right } => Ok(Scalar::Arithmetic(
+                Box::new(Scalar::new(left)?),
+                Arithmetic::new(op)?,
+                Box::new(Scalar::new(right)?),
+            )),
+            _ => match literal_to_string(expr) {
+                Some(value) => Ok(Scalar::Literal(value)),
+                None => Err(format!("Cannot parse expression {}", expr)),
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Assignment {
+    pub column: String,
+    pub value: Scalar,
+}
+
+#[derive(Debug)]
+pub struct UpdateQuery {
+    pub table_name: String,
+    pub assignments: Vec<Assignment>,
+    pub where_clause: Option<Predicate>,
+}
+
+impl UpdateQuery {
+    pub fn new(statement: &Statement) -> Result<UpdateQuery, String> {
+        match statement {
+            Statement::Update {
+                table,
+                assignments,
+                selection,
+                ..
+            } => {
+                let table_name = match &table.relation {
+                    TableFactor::Table { name, .. } => name.to_string(),
+                    _ => return Err("Can only update a single table".to_string()),
+                };
+
+                let mut parsed_assignments = vec![];
+                for assignment in assignments {
+                    let column = match assignment.id.last() {
+                        Some(col_name) => col_name.value.to_string(),
+                        None => return Err("Missing column name in SET".to_string()),
+                    };
+                    parsed_assignments.push(Assignment {
+                        column,
+                        value: Scalar::new(&assignment.value)?,
+                    });
+                }
+
+                let where_clause = match selection {
+                    Some(where_expression) => Some(Predicate::new(where_expression)?),
+                    None => None,
+                };
+
+                Ok(UpdateQuery {
+                    table_name,
+                    assignments: parsed_assignments,
+                    where_clause,
+                })
+            }
+            _ => Err("Error parsing update query".to_string()),
+        }
+    }
+    /// The columns the SET clause assigns, in the order it assigns them.
+    pub fn columns(&self) -> Vec<String> {
+        self.assignments
+            .iter()
+            .map(|assignment| assignment.column.to_string())
+            .collect()
+    }
+}
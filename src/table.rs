@@ -1,383 +1,935 @@
 use prettytable::{Cell, Row, Table as PTable};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt;
-use std::ops::Bound::{Excluded, Included, Unbounded};
 use std::result::Result;
 
 use crate::parser::{
-    create::CreateQuery,
-    select::{Binary, Expression, Operator, SelectQuery},
+    create::{CreateQuery, ForeignKeyAction},
+    delete::DeleteQuery,
+    index::CreateIndexQuery,
+    select::{
+        Aggregate, AggregateFunction, Binary, Expression, Operator, OrderBy, Predicate, Projection,
+        SelectQuery,
+    },
+    update::{Arithmetic, Scalar, UpdateQuery},
 };
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+mod alter;
+mod composite;
+mod constraint;
+mod decimal;
+pub mod join;
+mod temporal;
+
+use composite::{CompositeIndex, IndexKey};
+use constraint::CheckConstraint;
+pub use constraint::ForeignKey;
+use decimal::{Comparison, Decimal};
+
+/// New values of the rows an UPDATE changes, one list of values per assigned column.
+pub type NewValues = Vec<Vec<Option<String>>>;
+
+/// What a select query returns: the label and type of each column of the result, next to
+/// its values, one vector per column with `None` standing for NULL.
+#[derive(Debug)]
+pub struct QueryResult {
+    pub columns: Vec<(String, DataType)>,
+    pub data: Vec<Vec<Option<String>>>,
+}
+
+impl QueryResult {
+    /// The values of the result row by row.
+    pub fn rows(&self) -> Vec<Vec<Option<String>>> {
+        let row_count = self.data.first().map_or(0, |column| column.len());
+        (0..row_count)
+            .map(|row| self.data.iter().map(|column| column[row].clone()).collect())
+            .collect()
+    }
+
+    /// The values of the result column by column, with NULL written out.
+    pub fn display_data(&self) -> Vec<Vec<String>> {
+        self.data
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|val| val.clone().unwrap_or_else(|| "NULL".to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn print(&self) {
+        let header = self
+            .columns
+            .iter()
+            .map(|(label, _)| label.to_string())
+            .collect::<Vec<String>>();
+        let data = self.display_data();
+        util::pretty_print(&util::rotate_2d_vec(&data), &header);
+    }
+}
+
+/// Number of rows a LIMIT query without ORDER BY evaluates its where clause on at a time.
+const SCAN_BATCH_SIZE: usize = 1024;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum DataType {
+    TinyInt,
+    SmallInt,
     Int,
+    BigInt,
     Str,
     Float,
+    Decimal(Decimal),
     Bool,
+    Date,
+    Time,
+    DateTime,
+    Timestamp,
     Invalid,
 }
 
 impl DataType {
     pub fn new(cmd: String) -> DataType {
         match cmd.to_lowercase().as_ref() {
+            "tinyint" => DataType::TinyInt,
+            "smallint" => DataType::SmallInt,
             "int" => DataType::Int,
+            "bigint" => DataType::BigInt,
             "string" => DataType::Str,
             "float" => DataType::Float,
             "double" => DataType::Float,
             "bool" => DataType::Bool,
-            _ => {
-                println!("Invalid data type given {}", cmd);
-                return DataType::Invalid;
+            "date" => DataType::Date,
+            "time" => DataType::Time,
+            "datetime" => DataType::DateTime,
+            "timestamp" => DataType::Timestamp,
+            datatype if datatype.starts_with("decimal(") => {
+                // Spelled `decimal(precision,scale)`.
+                let parts = datatype["decimal(".len()..]
+                    .trim_end_matches(')')
+                    .split(',')
+                    .map(|part| part.trim().parse::<u32>())
+                    .collect::<Result<Vec<u32>, _>>();
+                match parts.as_deref() {
+                    Ok([precision, scale]) => DataType::Decimal(Decimal {
+                        precision: *precision,
+                        scale: *scale,
+                    }),
+                    _ => DataType::Invalid,
+                }
+            }
+            _ => DataType::Invalid,
+        }
+    }
+
+    /// The name `DataType::new` reads back as this type.
+    pub fn type_name(&self) -> String {
+        match self {
+            DataType::TinyInt => "tinyint".to_string(),
+            DataType::SmallInt => "smallint".to_string(),
+            DataType::Int => "int".to_string(),
+            DataType::BigInt => "bigint".to_string(),
+            DataType::Str => "string".to_string(),
+            DataType::Float => "float".to_string(),
+            DataType::Decimal(decimal) => {
+                format!("decimal({},{})", decimal.precision, decimal.scale)
             }
+            DataType::Bool => "bool".to_string(),
+            DataType::Date => "date".to_string(),
+            DataType::Time => "time".to_string(),
+            DataType::DateTime => "datetime".to_string(),
+            DataType::Timestamp => "timestamp".to_string(),
+            DataType::Invalid => "invalid".to_string(),
         }
     }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            DataType::TinyInt | DataType::SmallInt | DataType::Int | DataType::BigInt
+        )
+    }
 }
 
 impl fmt::Display for DataType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            DataType::TinyInt => f.write_str("TinyInt"),
+            DataType::SmallInt => f.write_str("SmallInt"),
             DataType::Int => f.write_str("Int"),
+            DataType::BigInt => f.write_str("BigInt"),
             DataType::Str => f.write_str("Str"),
             DataType::Float => f.write_str("Float"),
+            DataType::Decimal(decimal) => write!(f, "{}", decimal),
             DataType::Bool => f.write_str("Boolean"),
+            DataType::Date => f.write_str("Date"),
+            DataType::Time => f.write_str("Time"),
+            DataType::DateTime => f.write_str("DateTime"),
+            DataType::Timestamp => f.write_str("Timestamp"),
             DataType::Invalid => f.write_str("Invalid"),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ColumnHeader {
     pub name: String,
     pub datatype: DataType,
     pub is_indexed: bool,
     pub index: ColumnIndex,
+    pub index_name: Option<String>, // None for the primary key
+    pub is_unique: bool,
     pub is_primary_key: bool,
+    pub is_nullable: bool,
+    pub default: Option<String>,
+    pub auto_increment: Option<i64>, // the next value of an AUTO_INCREMENT column
 }
 
 impl ColumnHeader {
-    pub fn new(name: String, datatype: String, is_primary_key: bool) -> ColumnHeader {
+    pub fn new(
+        name: String,
+        datatype: String,
+        is_primary_key: bool,
+        is_nullable: bool,
+        default: Option<String>,
+    ) -> ColumnHeader {
         let dt = DataType::new(datatype);
-        let index = match dt {
-            DataType::Int => ColumnIndex::Int(BTreeMap::new()),
-            DataType::Float => ColumnIndex::None,
-            DataType::Str => ColumnIndex::Str(BTreeMap::new()),
-            DataType::Bool => ColumnIndex::Bool(BTreeMap::new()),
-            DataType::Invalid => ColumnIndex::None,
-        };
+        let index = ColumnIndex::new(&dt);
 
         ColumnHeader {
-            name: name,
+            name,
             datatype: dt,
-            is_indexed: if is_primary_key { true } else { false },
+            is_indexed: is_primary_key,
             index,
+            index_name: None,
+            is_unique: is_primary_key,
             is_primary_key,
+            is_nullable,
+            default,
+            auto_increment: None,
         }
     }
 
     pub fn get_mut_index(&mut self) -> &mut ColumnIndex {
-        return &mut self.index;
+        &mut self.index
+    }
+
+    fn unique_violation(&self, val: &str) -> String {
+        format!(
+            "Error: unique constraint violation for index {}. Value {} already exists for column {}",
+            self.index_name.as_deref().unwrap_or("PRIMARY"),
+            val,
+            self.name
+        )
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum ColumnData {
+    TinyInt(Vec<i8>),
+    SmallInt(Vec<i16>),
     Int(Vec<i32>),
+    BigInt(Vec<i64>),
     Str(Vec<String>),
     Float(Vec<f32>),
+    Decimal(Vec<i128>, Decimal),
     Bool(Vec<bool>),
+    Date(Vec<i32>),     // days since 1970-01-01
+    Time(Vec<i32>),     // seconds since midnight
+    DateTime(Vec<i64>), // seconds since 1970-01-01 00:00:00, for TIMESTAMP too
     None,
 }
 
 impl ColumnData {
-    fn get_serialized_col_data(&self) -> Vec<String> {
-        match self {
-            ColumnData::Int(cd) => cd.iter().map(|v| v.to_string()).collect(),
-            ColumnData::Float(cd) => cd.iter().map(|v| v.to_string()).collect(),
-            ColumnData::Str(cd) => cd.iter().map(|v| v.to_string()).collect(),
-            ColumnData::Bool(cd) => cd.iter().map(|v| v.to_string()).collect(),
-            ColumnData::None => panic!("Found None in columns"),
+    /// An empty column for values of `datatype`.
+    fn new(datatype: &DataType) -> ColumnData {
+        match datatype {
+            DataType::TinyInt => ColumnData::TinyInt(vec![]),
+            DataType::SmallInt => ColumnData::SmallInt(vec![]),
+            DataType::Int => ColumnData::Int(vec![]),
+            DataType::BigInt => ColumnData::BigInt(vec![]),
+            DataType::Float => ColumnData::Float(vec![]),
+            DataType::Decimal(decimal) => ColumnData::Decimal(vec![], *decimal),
+            DataType::Str => ColumnData::Str(vec![]),
+            DataType::Bool => ColumnData::Bool(vec![]),
+            DataType::Date => ColumnData::Date(vec![]),
+            DataType::Time => ColumnData::Time(vec![]),
+            DataType::DateTime | DataType::Timestamp => ColumnData::DateTime(vec![]),
+            DataType::Invalid => ColumnData::None,
         }
     }
 
-    fn get_serialized_col_data_by_index(&self, indices: &Vec<usize>) -> Vec<String> {
-        let mut selected_data = vec![];
+    fn get_serialized_col_data_by_scanning(
+        &self,
+        expr: &Expression,
+        rows: &[usize],
+    ) -> Result<Vec<usize>, String> {
+        let search_term = &expr.right;
+        let Operator::Binary(op) = &expr.op;
         match self {
-            ColumnData::Int(cd) => {
-                for i in indices {
-                    selected_data.push((cd[*i]).to_string());
-                }
-            }
+            ColumnData::TinyInt(cd) => util::scan_widened(cd, rows, search_term, op),
+            ColumnData::SmallInt(cd) => util::scan_widened(cd, rows, search_term, op),
+            ColumnData::Int(cd) => util::scan_widened(cd, rows, search_term, op),
+            ColumnData::BigInt(cd) => util::scan_widened(cd, rows, search_term, op),
             ColumnData::Float(cd) => {
-                for i in indices {
-                    selected_data.push((cd[*i]).to_string());
-                }
+                let FloatKey(search_term) = FloatKey::parse(search_term)?;
+                Ok(util::scan(cd, rows, &search_term, op))
             }
-            ColumnData::Str(cd) => {
-                for i in indices {
-                    selected_data.push(cd[*i].to_string());
-                }
+            ColumnData::Decimal(cd, decimal) => {
+                let comparison = decimal.comparison(op, search_term)?;
+                Ok(rows
+                    .iter()
+                    .copied()
+                    .filter(|idx| comparison.matches(cd[*idx]))
+                    .collect())
             }
+            ColumnData::Str(cd) => Ok(util::scan(cd, rows, search_term, op)),
             ColumnData::Bool(cd) => {
-                for i in indices {
-                    selected_data.push(cd[*i].to_string());
-                }
+                let search_term = util::parse_bool(search_term)?;
+                Ok(util::scan(cd, rows, &search_term, op))
+            }
+            ColumnData::Date(cd) => {
+                let search_term = temporal::parse_date(search_term)?;
+                Ok(util::scan(cd, rows, &search_term, op))
+            }
+            ColumnData::Time(cd) => {
+                let search_term = temporal::parse_time(search_term)?;
+                Ok(util::scan(cd, rows, &search_term, op))
+            }
+            ColumnData::DateTime(cd) => {
+                let search_term = temporal::parse_datetime(search_term)?;
+                Ok(util::scan(cd, rows, &search_term, op))
             }
             ColumnData::None => panic!("Found None in columns"),
         }
-        selected_data
     }
 
-    fn get_serialized_col_data_by_scanning(&self, expr: &Expression) -> Vec<usize> {
-        let search_term = (&expr.right).to_string();
-        let mut scanned_vals = vec![];
-        match &expr.op {
-            Operator::Binary(binary_op) => match binary_op {
-                Binary::Eq => match self {
-                    ColumnData::Int(cd) => {
-                        let search_term = search_term.parse::<i32>().unwrap();
+    fn get_serialized_value(&self, row: usize) -> String {
+        match self {
+            ColumnData::TinyInt(cd) => cd[row].to_string(),
+            ColumnData::SmallInt(cd) => cd[row].to_string(),
+            ColumnData::Int(cd) => cd[row].to_string(),
+            ColumnData::BigInt(cd) => cd[row].to_string(),
+            ColumnData::Float(cd) => cd[row].to_string(),
+            ColumnData::Decimal(cd, decimal) => decimal.format(cd[row]),
+            ColumnData::Str(cd) => cd[row].to_string(),
+            ColumnData::Bool(cd) => cd[row].to_string(),
+            ColumnData::Date(cd) => temporal::format_date(cd[row]),
+            ColumnData::Time(cd) => temporal::format_time(cd[row]),
+            ColumnData::DateTime(cd) => temporal::format_datetime(cd[row]),
+            ColumnData::None => panic!("Found None in columns"),
+        }
+    }
 
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i == search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::Float(cd) => {
-                        let search_term = search_term.parse::<f32>().unwrap();
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i == search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::Str(cd) => {
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i == search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::Bool(cd) => {
-                        let search_term = search_term.parse::<bool>().unwrap();
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i == search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::None => panic!("Found None in columns"),
-                },
-                Binary::Gt => match self {
-                    ColumnData::Int(cd) => {
-                        let search_term = search_term.parse::<i32>().unwrap();
-
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i > search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::Float(cd) => {
-                        let search_term = search_term.parse::<f32>().unwrap();
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i > search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::Str(cd) => {
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i > search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::Bool(cd) => {
-                        let search_term = search_term.parse::<bool>().unwrap();
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i > search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::None => panic!("Found None in columns"),
-                },
-                Binary::Lt => match self {
-                    ColumnData::Int(cd) => {
-                        let search_term = search_term.parse::<i32>().unwrap();
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i < search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::Float(cd) => {
-                        let mut scanned_vals = vec![];
-                        let search_term = search_term.parse::<f32>().unwrap();
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i < search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::Str(cd) => {
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i < search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::Bool(cd) => {
-                        let search_term = search_term.parse::<bool>().unwrap();
-                        for (idx, i) in cd.iter().enumerate() {
-                            if *i < search_term {
-                                scanned_vals.push(idx);
-                            }
-                        }
-                        return scanned_vals;
-                    }
-                    ColumnData::None => panic!("Found None in columns"),
-                },
-            },
+    /// Parses `val` the way this column would store it and renders it back, so equal values
+    /// always come out as the same string.
+    fn normalize_value(&self, val: &str) -> Result<String, String> {
+        let (parsed, datatype) = match self {
+            ColumnData::TinyInt(_) => (val.parse::<i8>().map(|v| v.to_string()).ok(), "TinyInt"),
+            ColumnData::SmallInt(_) => (val.parse::<i16>().map(|v| v.to_string()).ok(), "SmallInt"),
+            ColumnData::Int(_) => (val.parse::<i32>().map(|v| v.to_string()).ok(), "Int"),
+            ColumnData::BigInt(_) => (val.parse::<i64>().map(|v| v.to_string()).ok(), "BigInt"),
+            // NaN and infinities, spelled out or from a literal too large for an f32, would
+            // compare differently through an index than through a scan.
+            ColumnData::Float(_) => (
+                val.parse::<f32>()
+                    .ok()
+                    .filter(|v| v.is_finite())
+                    .map(|v| v.to_string()),
+                "Float",
+            ),
+            // Tells an out of range value apart from one that isn't a number.
+            ColumnData::Decimal(_, decimal) => {
+                return decimal.parse(val).map(|v| decimal.format(v))
+            }
+            ColumnData::Str(_) => (Some(val.to_string()), "Str"),
+            ColumnData::Bool(_) => (util::parse_bool(val).map(|v| v.to_string()).ok(), "Boolean"),
+            ColumnData::Date(_) => (
+                temporal::parse_date(val).map(temporal::format_date).ok(),
+                "Date",
+            ),
+            ColumnData::Time(_) => (
+                temporal::parse_time(val).map(temporal::format_time).ok(),
+                "Time",
+            ),
+            ColumnData::DateTime(_) => (
+                temporal::parse_datetime(val)
+                    .map(temporal::format_datetime)
+                    .ok(),
+                "DateTime",
+            ),
+            ColumnData::None => panic!("Found None in columns"),
+        };
+        parsed.ok_or_else(|| format!("cannot store {} in a column of type {}", val, datatype))
+    }
+
+    fn set_value(&mut self, row: usize, val: &str) -> Result<(), String> {
+        let val = self.normalize_value(val)?;
+        match self {
+            ColumnData::TinyInt(cd) => cd[row] = val.parse::<i8>().unwrap(),
+            ColumnData::SmallInt(cd) => cd[row] = val.parse::<i16>().unwrap(),
+            ColumnData::Int(cd) => cd[row] = val.parse::<i32>().unwrap(),
+            ColumnData::BigInt(cd) => cd[row] = val.parse::<i64>().unwrap(),
+            ColumnData::Float(cd) => cd[row] = val.parse::<f32>().unwrap(),
+            ColumnData::Decimal(cd, decimal) => cd[row] = decimal.parse(&val)?,
+            ColumnData::Str(cd) => cd[row] = val,
+            ColumnData::Bool(cd) => cd[row] = val.parse::<bool>().unwrap(),
+            ColumnData::Date(cd) => cd[row] = temporal::parse_date(&val)?,
+            ColumnData::Time(cd) => cd[row] = temporal::parse_time(&val)?,
+            ColumnData::DateTime(cd) => cd[row] = temporal::parse_datetime(&val)?,
+            ColumnData::None => panic!("Found None in columns"),
+        }
+        Ok(())
+    }
+
+    /// Appends `val`, or a placeholder standing in for NULL when there is none.
+    fn push_value(&mut self, val: Option<&str>) -> Result<(), String> {
+        let val = match val {
+            Some(val) => Some(self.normalize_value(val)?),
+            None => None,
+        };
+        match self {
+            ColumnData::TinyInt(cd) => cd.push(val.map_or(0, |v| v.parse::<i8>().unwrap())),
+            ColumnData::SmallInt(cd) => cd.push(val.map_or(0, |v| v.parse::<i16>().unwrap())),
+            ColumnData::Int(cd) => cd.push(val.map_or(0, |v| v.parse::<i32>().unwrap())),
+            ColumnData::BigInt(cd) => cd.push(val.map_or(0, |v| v.parse::<i64>().unwrap())),
+            ColumnData::Float(cd) => cd.push(val.map_or(0.0, |v| v.parse::<f32>().unwrap())),
+            ColumnData::Decimal(cd, decimal) => cd.push(val.map_or(Ok(0), |v| decimal.parse(&v))?),
+            ColumnData::Str(cd) => cd.push(val.unwrap_or_default()),
+            ColumnData::Bool(cd) => cd.push(val.is_some_and(|v| v.parse::<bool>().unwrap())),
+            ColumnData::Date(cd) => cd.push(val.map_or(Ok(0), |v| temporal::parse_date(&v))?),
+            ColumnData::Time(cd) => cd.push(val.map_or(Ok(0), |v| temporal::parse_time(&v))?),
+            ColumnData::DateTime(cd) => {
+                cd.push(val.map_or(Ok(0), |v| temporal::parse_datetime(&v))?)
+            }
+            ColumnData::None => panic!("Found None in columns"),
+        }
+        Ok(())
+    }
+
+    /// Drops the rows at the sorted positions in `deleted`, later rows move up to fill the gaps.
+    fn remove_rows(&mut self, deleted: &[usize]) {
+        let mut is_deleted = vec![false; self.count()];
+        for row in deleted {
+            is_deleted[*row] = true;
+        }
+        let mut row = 0;
+        let mut keep = || {
+            row += 1;
+            !is_deleted[row - 1]
+        };
+        match self {
+            ColumnData::TinyInt(cd) => cd.retain(|_| keep()),
+            ColumnData::SmallInt(cd) => cd.retain(|_| keep()),
+            ColumnData::Int(cd) => cd.retain(|_| keep()),
+            ColumnData::BigInt(cd) => cd.retain(|_| keep()),
+            ColumnData::Float(cd) => cd.retain(|_| keep()),
+            ColumnData::Decimal(cd, _) => cd.retain(|_| keep()),
+            ColumnData::Str(cd) => cd.retain(|_| keep()),
+            ColumnData::Bool(cd) => cd.retain(|_| keep()),
+            ColumnData::Date(cd) => cd.retain(|_| keep()),
+            ColumnData::Time(cd) => cd.retain(|_| keep()),
+            ColumnData::DateTime(cd) => cd.retain(|_| keep()),
+            ColumnData::None => panic!("Found None in columns"),
+        }
+    }
+
+    fn compare_rows(&self, a: usize, b: usize) -> Ordering {
+        match self {
+            ColumnData::TinyInt(cd) => cd[a].cmp(&cd[b]),
+            ColumnData::SmallInt(cd) => cd[a].cmp(&cd[b]),
+            ColumnData::Int(cd) => cd[a].cmp(&cd[b]),
+            ColumnData::BigInt(cd) => cd[a].cmp(&cd[b]),
+            ColumnData::Float(cd) => cd[a].partial_cmp(&cd[b]).unwrap_or(Ordering::Equal),
+            ColumnData::Decimal(cd, _) => cd[a].cmp(&cd[b]),
+            ColumnData::Str(cd) => cd[a].cmp(&cd[b]),
+            ColumnData::Bool(cd) => cd[a].cmp(&cd[b]),
+            ColumnData::Date(cd) => cd[a].cmp(&cd[b]),
+            ColumnData::Time(cd) => cd[a].cmp(&cd[b]),
+            ColumnData::DateTime(cd) => cd[a].cmp(&cd[b]),
+            ColumnData::None => panic!("Found None in columns"),
+        }
+    }
+
+    /// Sum of the values at `rows`, `None` for a column that isn't numeric.
+    fn sum(&self, rows: &[usize]) -> Option<Sum> {
+        match self {
+            ColumnData::TinyInt(cd) => Some(util::exact_sum(cd, rows)),
+            ColumnData::SmallInt(cd) => Some(util::exact_sum(cd, rows)),
+            ColumnData::Int(cd) => Some(util::exact_sum(cd, rows)),
+            ColumnData::BigInt(cd) => Some(util::exact_sum(cd, rows)),
+            ColumnData::Float(cd) => Some(Sum::Float(rows.iter().map(|idx| cd[*idx] as f64).sum())),
+            _ => None,
         }
     }
 
     fn count(&self) -> usize {
         match self {
+            ColumnData::TinyInt(cd) => cd.len(),
+            ColumnData::SmallInt(cd) => cd.len(),
             ColumnData::Int(cd) => cd.len(),
+            ColumnData::BigInt(cd) => cd.len(),
             ColumnData::Float(cd) => cd.len(),
+            ColumnData::Decimal(cd, _) => cd.len(),
             ColumnData::Str(cd) => cd.len(),
             ColumnData::Bool(cd) => cd.len(),
+            ColumnData::Date(cd) => cd.len(),
+            ColumnData::Time(cd) => cd.len(),
+            ColumnData::DateTime(cd) => cd.len(),
             ColumnData::None => panic!("Found None in columns"),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// The values of a column next to its validity bitmap. A NULL row keeps a placeholder in
+/// `data` so that row positions line up across columns, `nulls` tells it apart.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Column {
+    pub data: ColumnData,
+    pub nulls: Vec<bool>,
+}
+
+impl Column {
+    pub fn new(data: ColumnData) -> Column {
+        Column {
+            data,
+            nulls: vec![],
+        }
+    }
+
+    fn get_serialized_col_data(&self) -> Vec<String> {
+        (0..self.count())
+            .map(|row| self.get_serialized_value(row))
+            .collect()
+    }
+
+    /// Positions out of `rows` satisfying `expr`. NULL satisfies no comparison.
+    fn get_serialized_col_data_by_scanning(
+        &self,
+        expr: &Expression,
+        rows: &[usize],
+    ) -> Result<Vec<usize>, String> {
+        let rows = rows
+            .iter()
+            .copied()
+            .filter(|row| !self.is_null(*row))
+            .collect::<Vec<usize>>();
+        self.data.get_serialized_col_data_by_scanning(expr, &rows)
+    }
+
+    /// The value at `row`, `None` when it is NULL.
+    fn get_value(&self, row: usize) -> Option<String> {
+        match self.is_null(row) {
+            true => None,
+            false => Some(self.data.get_serialized_value(row)),
+        }
+    }
+
+    /// The value at `row` the way it is printed.
+    fn get_serialized_value(&self, row: usize) -> String {
+        self.get_value(row).unwrap_or_else(|| "NULL".to_string())
+    }
+
+    fn normalize_value(&self, val: &str) -> Result<String, String> {
+        self.data.normalize_value(val)
+    }
+
+    fn set_value(&mut self, row: usize, val: Option<&str>) -> Result<(), String> {
+        if let Some(val) = val {
+            self.data.set_value(row, val)?;
+        }
+        self.nulls[row] = val.is_none();
+        Ok(())
+    }
+
+    fn push_value(&mut self, val: Option<&str>) -> Result<(), String> {
+        self.data.push_value(val)?;
+        self.nulls.push(val.is_none());
+        Ok(())
+    }
+
+    /// Drops the rows at the sorted positions in `deleted`, later rows move up to fill the gaps.
+    fn remove_rows(&mut self, deleted: &[usize]) {
+        self.data.remove_rows(deleted);
+        let mut row = 0;
+        self.nulls.retain(|_| {
+            row += 1;
+            deleted.binary_search(&(row - 1)).is_err()
+        });
+    }
+
+    fn is_null(&self, row: usize) -> bool {
+        self.nulls[row]
+    }
+
+    /// Orders two rows of this column by their values, NULL being the smallest value.
+    fn compare_rows(&self, a: usize, b: usize) -> Ordering {
+        match (self.is_null(a), self.is_null(b)) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => self.data.compare_rows(a, b),
+        }
+    }
+
+    /// Orders two rows of this column the way the ORDER BY key `key` wants them.
+    fn compare_rows_by_key(&self, a: usize, b: usize, key: &OrderBy) -> Ordering {
+        let (a_is_null, b_is_null) = (self.is_null(a), self.is_null(b));
+        if a_is_null != b_is_null {
+            // NULL placement doesn't flip with the direction.
+            if a_is_null == key.nulls_first {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        } else if key.asc {
+            self.compare_rows(a, b)
+        } else {
+            self.compare_rows(a, b).reverse()
+        }
+    }
+
+    fn sum(&self, rows: &[usize]) -> Option<Sum> {
+        self.data.sum(rows)
+    }
+
+    fn count(&self) -> usize {
+        self.nulls.len()
+    }
+}
+
+/// The sum of a numeric column, kept exact for integer columns.
+enum Sum {
+    Integer(i128),
+    Float(f64),
+}
+
+/// An f32 ordered by `f32::total_cmp`, so that float values can key an index.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct FloatKey(f32);
+
+impl FloatKey {
+    fn new(val: f32) -> FloatKey {
+        // -0.0 sorts below 0.0 in the total order, yet they are equal values.
+        FloatKey(if val == 0.0 { 0.0 } else { val })
+    }
+
+    /// Parses a value or a where clause term. NaN is refused, it compares to nothing yet
+    /// `total_cmp` sorts it above every number.
+    fn parse(val: &str) -> Result<FloatKey, String> {
+        match val.parse::<f32>() {
+            Ok(val) if val.is_nan() => Err("NaN cannot be compared".to_string()),
+            Ok(val) => Ok(FloatKey::new(val)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+impl PartialEq for FloatKey {
+    fn eq(&self, other: &FloatKey) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FloatKey {}
+
+impl PartialOrd for FloatKey {
+    fn partial_cmp(&self, other: &FloatKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FloatKey {
+    fn cmp(&self, other: &FloatKey) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Maps every key to the sorted positions of the rows holding it. A unique index has a single
+/// position per key.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum ColumnIndex {
-    Int(BTreeMap<i32, usize>),
-    Str(BTreeMap<String, usize>),
-    Bool(BTreeMap<bool, usize>),
+    Int(BTreeMap<i64, Vec<usize>>),
+    Str(BTreeMap<String, Vec<usize>>),
+    Float(BTreeMap<FloatKey, Vec<usize>>),
+    Decimal(BTreeMap<i128, Vec<usize>>, Decimal),
+    Bool(BTreeMap<bool, Vec<usize>>),
+    Date(BTreeMap<i32, Vec<usize>>),
+    Time(BTreeMap<i32, Vec<usize>>),
+    DateTime(BTreeMap<i64, Vec<usize>>),
     None,
 }
 
 impl ColumnIndex {
-    fn get_idx_data(&self, val: &String) -> Result<Option<&usize>, String> {
+    /// An empty index for values of `datatype`.
+    fn new(datatype: &DataType) -> ColumnIndex {
+        match datatype {
+            // Integers of every width share i64 keys.
+            DataType::TinyInt | DataType::SmallInt | DataType::Int | DataType::BigInt => {
+                ColumnIndex::Int(BTreeMap::new())
+            }
+            DataType::Float => ColumnIndex::Float(BTreeMap::new()),
+            DataType::Decimal(decimal) => ColumnIndex::Decimal(BTreeMap::new(), *decimal),
+            DataType::Str => ColumnIndex::Str(BTreeMap::new()),
+            DataType::Bool => ColumnIndex::Bool(BTreeMap::new()),
+            DataType::Date => ColumnIndex::Date(BTreeMap::new()),
+            DataType::Time => ColumnIndex::Time(BTreeMap::new()),
+            DataType::DateTime | DataType::Timestamp => ColumnIndex::DateTime(BTreeMap::new()),
+            DataType::Invalid => ColumnIndex::None,
+        }
+    }
+
+    /// Sorted positions of the rows holding `val`.
+    fn get_idx_data(&self, val: &str) -> Result<&[usize], String> {
         match self {
-            ColumnIndex::Int(index) => match val.parse::<i32>() {
-                Ok(val) => Ok(index.get(&val)),
+            ColumnIndex::Int(index) => match val.parse::<i64>() {
+                Ok(val) => Ok(util::index_get(index, &val)),
                 Err(e) => Err(e.to_string()),
             },
 
-            ColumnIndex::Bool(index) => match val.parse::<bool>() {
-                Ok(val) => Ok(index.get(&val)),
-                Err(e) => Err(e.to_string()),
+            ColumnIndex::Bool(index) => Ok(util::index_get(index, &util::parse_bool(val)?)),
+            ColumnIndex::Str(index) => Ok(util::index_get(index, val)),
+            ColumnIndex::Float(index) => Ok(util::index_get(index, &FloatKey::parse(val)?)),
+            ColumnIndex::Decimal(index, decimal) => match decimal.comparison(&Binary::Eq, val)? {
+                Comparison::Term(_, key) => Ok(util::index_get(index, &key)),
+                _ => Ok(&[]),
             },
-            ColumnIndex::Str(index) => Ok(index.get(val)),
+            ColumnIndex::Date(index) => Ok(util::index_get(index, &temporal::parse_date(val)?)),
+            ColumnIndex::Time(index) => Ok(util::index_get(index, &temporal::parse_time(val)?)),
+            ColumnIndex::DateTime(index) => {
+                Ok(util::index_get(index, &temporal::parse_datetime(val)?))
+            }
 
-            ColumnIndex::None => Ok(None),
+            ColumnIndex::None => Ok(&[]),
         }
     }
 
-    fn get_idx_data_by_range(&self, val: &String, op: Binary) -> Result<Vec<usize>, String> {
-        let mut indexes: Vec<usize> = vec![];
+    fn insert_value(&mut self, val: &str, row: usize) -> Result<(), String> {
         match self {
-            ColumnIndex::Int(index) => match val.parse::<i32>() {
-                Ok(val) => {
-                    for (_key, idx) in index.range(if Binary::Gt == op {
-                        (Excluded(val), Unbounded)
-                    } else if Binary::Lt == op {
-                        (Unbounded, Excluded(val))
-                    } else {
-                        (Included(val), Included(val))
-                    }) {
-                        indexes.push(*idx);
-                    }
-                    Ok(indexes)
-                }
-                Err(e) => Err(e.to_string()),
+            ColumnIndex::Int(index) => match val.parse::<i64>() {
+                Ok(val) => util::index_insert(index, val, row),
+                Err(e) => return Err(e.to_string()),
             },
+            ColumnIndex::Bool(index) => util::index_insert(index, util::parse_bool(val)?, row),
+            ColumnIndex::Str(index) => util::index_insert(index, val.to_string(), row),
+            ColumnIndex::Float(index) => util::index_insert(index, FloatKey::parse(val)?, row),
+            ColumnIndex::Decimal(index, decimal) => {
+                util::index_insert(index, decimal.parse(val)?, row)
+            }
+            ColumnIndex::Date(index) => util::index_insert(index, temporal::parse_date(val)?, row),
+            ColumnIndex::Time(index) => util::index_insert(index, temporal::parse_time(val)?, row),
+            ColumnIndex::DateTime(index) => {
+                util::index_insert(index, temporal::parse_datetime(val)?, row)
+            }
+            ColumnIndex::None => {}
+        };
+        Ok(())
+    }
 
-            ColumnIndex::Bool(index) => match val.parse::<bool>() {
-                Ok(val) => {
-                    for (_key, idx) in index.range(if Binary::Gt == op {
-                        (Excluded(val), Unbounded)
-                    } else if Binary::Lt == op {
-                        (Unbounded, Excluded(val))
-                    } else {
-                        (Included(val), Included(val))
-                    }) {
-                        indexes.push(*idx);
-                    }
-                    Ok(indexes)
-                }
-                Err(e) => Err(e.to_string()),
+    /// Forgets that `row` holds `val`.
+    fn remove_value(&mut self, val: &str, row: usize) -> Result<(), String> {
+        match self {
+            ColumnIndex::Int(index) => match val.parse::<i64>() {
+                Ok(val) => util::index_remove(index, &val, row),
+                Err(e) => return Err(e.to_string()),
             },
+            ColumnIndex::Bool(index) => util::index_remove(index, &util::parse_bool(val)?, row),
+            ColumnIndex::Str(index) => util::index_remove(index, val, row),
+            ColumnIndex::Float(index) => util::index_remove(index, &FloatKey::parse(val)?, row),
+            ColumnIndex::Decimal(index, decimal) => {
+                util::index_remove(index, &decimal.parse(val)?, row)
+            }
+            ColumnIndex::Date(index) => util::index_remove(index, &temporal::parse_date(val)?, row),
+            ColumnIndex::Time(index) => util::index_remove(index, &temporal::parse_time(val)?, row),
+            ColumnIndex::DateTime(index) => {
+                util::index_remove(index, &temporal::parse_datetime(val)?, row)
+            }
+            ColumnIndex::None => {}
+        };
+        Ok(())
+    }
+
+    /// Forgets the keys of the rows at the sorted positions in `deleted` and shifts the
+    /// positions of the remaining rows the same way `ColumnData::remove_rows` does.
+    fn remove_rows(&mut self, deleted: &[usize]) {
+        match self {
+            ColumnIndex::Int(index) => util::index_remove_rows(index, deleted),
+            ColumnIndex::Str(index) => util::index_remove_rows(index, deleted),
+            ColumnIndex::Float(index) => util::index_remove_rows(index, deleted),
+            ColumnIndex::Decimal(index, _) => util::index_remove_rows(index, deleted),
+            ColumnIndex::Bool(index) => util::index_remove_rows(index, deleted),
+            ColumnIndex::Date(index) => util::index_remove_rows(index, deleted),
+            ColumnIndex::Time(index) => util::index_remove_rows(index, deleted),
+            ColumnIndex::DateTime(index) => util::index_remove_rows(index, deleted),
+            ColumnIndex::None => {}
+        }
+    }
+
+    /// The row positions held by the index, one slice per key, in key order.
+    fn get_row_groups_in_order(&self) -> Vec<&[usize]> {
+        match self {
+            ColumnIndex::Int(index) => index.values().map(Vec::as_slice).collect(),
+            ColumnIndex::Str(index) => index.values().map(Vec::as_slice).collect(),
+            ColumnIndex::Float(index) => index.values().map(Vec::as_slice).collect(),
+            ColumnIndex::Decimal(index, _) => index.values().map(Vec::as_slice).collect(),
+            ColumnIndex::Bool(index) => index.values().map(Vec::as_slice).collect(),
+            ColumnIndex::Date(index) => index.values().map(Vec::as_slice).collect(),
+            ColumnIndex::Time(index) => index.values().map(Vec::as_slice).collect(),
+            ColumnIndex::DateTime(index) => index.values().map(Vec::as_slice).collect(),
+            ColumnIndex::None => vec![],
+        }
+    }
+
+    /// The first row in key order for which `is_wanted` holds, searching from the largest key
+    /// when `from_end` is set.
+    fn find_first_row(&self, from_end: bool, is_wanted: impl Fn(usize) -> bool) -> Option<usize> {
+        match self {
+            ColumnIndex::Int(index) => {
+                util::find_first_row(index.values().flatten(), from_end, is_wanted)
+            }
             ColumnIndex::Str(index) => {
-                for (_key, idx) in index.range(if Binary::Gt == op {
-                    (Excluded(val.to_string()), Unbounded)
-                } else if Binary::Lt == op {
-                    (Unbounded, Excluded(val.to_string()))
-                } else {
-                    (Included(val.to_string()), Included(val.to_string()))
-                }) {
-                    indexes.push(*idx);
-                }
-                Ok(indexes)
+                util::find_first_row(index.values().flatten(), from_end, is_wanted)
+            }
+            ColumnIndex::Float(index) => {
+                util::find_first_row(index.values().flatten(), from_end, is_wanted)
+            }
+            ColumnIndex::Decimal(index, _) => {
+                util::find_first_row(index.values().flatten(), from_end, is_wanted)
+            }
+            ColumnIndex::Bool(index) => {
+                util::find_first_row(index.values().flatten(), from_end, is_wanted)
+            }
+            ColumnIndex::Date(index) => {
+                util::find_first_row(index.values().flatten(), from_end, is_wanted)
+            }
+            ColumnIndex::Time(index) => {
+                util::find_first_row(index.values().flatten(), from_end, is_wanted)
+            }
+            ColumnIndex::DateTime(index) => {
+                util::find_first_row(index.values().flatten(), from_end, is_wanted)
+            }
+            ColumnIndex::None => None,
+        }
+    }
+
+    fn get_idx_data_by_range(&self, val: &str, op: &Binary) -> Result<Vec<usize>, String> {
+        match self {
+            ColumnIndex::Int(index) => match val.parse::<i64>() {
+                Ok(val) => Ok(util::range(index, val, op)),
+                Err(e) => Err(e.to_string()),
+            },
+            ColumnIndex::Bool(index) => Ok(util::range(index, util::parse_bool(val)?, op)),
+            ColumnIndex::Str(index) => Ok(util::range(index, val.to_string(), op)),
+            ColumnIndex::Float(index) => Ok(util::range(index, FloatKey::parse(val)?, op)),
+            ColumnIndex::Decimal(index, decimal) => Ok(match decimal.comparison(op, val)? {
+                Comparison::Term(op, key) => util::range(index, key, &op),
+                Comparison::All => index.values().flatten().copied().collect(),
+                Comparison::Nothing => vec![],
+            }),
+            ColumnIndex::Date(index) => Ok(util::range(index, temporal::parse_date(val)?, op)),
+            ColumnIndex::Time(index) => Ok(util::range(index, temporal::parse_time(val)?, op)),
+            ColumnIndex::DateTime(index) => {
+                Ok(util::range(index, temporal::parse_datetime(val)?, op))
             }
-            ColumnIndex::None => Ok(indexes),
+            ColumnIndex::None => Ok(vec![]),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Table {
     pub columns: Vec<ColumnHeader>,
     pub name: String,
-    pub rows: HashMap<String, ColumnData>,
+    pub rows: HashMap<String, Column>,
+    pub indexes: Vec<CompositeIndex>, // indexes over several columns
+    pub checks: Vec<CheckConstraint>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub auto_increment_start: i64, // the first value of its AUTO_INCREMENT column
 }
 
 impl Table {
-    pub fn new(cq: CreateQuery) -> Table {
+    pub fn new(cq: CreateQuery) -> Result<Table, String> {
         let table_name = cq.table_name;
         let columns = cq.columns;
 
         let mut table_cols: Vec<ColumnHeader> = vec![];
-        let mut table_data: HashMap<String, ColumnData> = HashMap::new();
+        let mut table_data: HashMap<String, Column> = HashMap::new();
+        if columns.iter().filter(|c| c.is_auto_increment).count() > 1 {
+            return Err("A table can only have one AUTO_INCREMENT column".to_string());
+        }
         for c in &columns {
-            table_cols.push(ColumnHeader::new(
+            let mut header = ColumnHeader::new(
                 c.name.to_string(),
                 c.datatype.to_string(),
                 c.is_pk,
-            ));
+                c.is_nullable,
+                c.default.clone(),
+            );
+            if c.is_auto_increment {
+                if !header.datatype.is_integer() {
+                    return Err(format!(
+                        "AUTO_INCREMENT column {} must be an integer, not {}",
+                        c.name, header.datatype
+                    ));
+                }
+                header.auto_increment = Some(cq.auto_increment_start.map_or(1, i64::from));
+            }
+            table_cols.push(header);
 
-            match DataType::new(c.datatype.to_string()) {
-                DataType::Int => table_data.insert(c.name.to_string(), ColumnData::Int(vec![])),
-                DataType::Float => table_data.insert(c.name.to_string(), ColumnData::Float(vec![])),
-                DataType::Str => table_data.insert(c.name.to_string(), ColumnData::Str(vec![])),
-                DataType::Bool => table_data.insert(c.name.to_string(), ColumnData::Bool(vec![])),
-                DataType::Invalid => table_data.insert(c.name.to_string(), ColumnData::None),
-            };
+            let data = ColumnData::new(&DataType::new(c.datatype.to_string()));
+            table_data.insert(c.name.to_string(), Column::new(data));
         }
 
-        Table {
+        let mut table = Table {
             columns: table_cols,
             name: table_name.to_string(),
             rows: table_data,
+            indexes: vec![],
+            checks: vec![],
+            foreign_keys: vec![],
+            auto_increment_start: cq.auto_increment_start.map_or(1, i64::from),
+        };
+        if !cq.primary_key.is_empty() {
+            let mut primary_key = CompositeIndex::new("PRIMARY", &cq.primary_key, true);
+            primary_key.is_primary = true;
+            table.indexes.push(primary_key);
+        }
+        for index in &cq.indexes {
+            table.add_index(&index.name, &index.columns, index.is_unique)?;
+        }
+        for check in &cq.checks {
+            table.add_check(check)?;
+        }
+        for fk in cq.foreign_keys {
+            let parent_column = match fk.parent_column {
+                Some(parent_column) => parent_column,
+                None => return Err(format!("foreign key {} names no column", fk.name)),
+            };
+            let is_nullable = table.get_column(fk.column.to_string()).is_nullable;
+            if !is_nullable
+                && (fk.on_delete == ForeignKeyAction::SetNull
+                    || fk.on_update == ForeignKeyAction::SetNull)
+            {
+                return Err(format!(
+                    "foreign key {} cannot SET NULL, column {} is NOT NULL",
+                    fk.name, fk.column
+                ));
+            }
+            table.foreign_keys.push(ForeignKey {
+                name: fk.name,
+                column: fk.column,
+                parent_table: fk.parent_table,
+                parent_column,
+                on_delete: fk.on_delete,
+                on_update: fk.on_update,
+            });
         }
+        Ok(table)
     }
 
     pub fn get_column(&self, col_name: String) -> &ColumnHeader {
@@ -391,231 +943,1015 @@ impl Table {
 
     pub fn does_violate_unique_constraint(
         &self,
-        cols: &Vec<String>,
-        values: &Vec<String>,
+        cols: &[String],
+        values: &[Option<String>],
     ) -> Result<(), String> {
-        for c in &self.columns {
-            if c.is_primary_key {
-                let col_idx = &c.index;
-                for (idx, name) in cols.iter().enumerate() {
-                    if *name == c.name {
-                        let val = &values[idx];
-
-                        match col_idx {
-                            ColumnIndex::Int(index) => {
-                                match index.contains_key(&val.parse::<i32>().unwrap()) {
-                                    true => {
-                                        return Err(format!(
-                                            "Error: unique constraint violation for column {}.
-                            Value {} already exists for column {}",
-                                            *name, val, *name
-                                        ));
-                                    }
-                                    false => return Ok(()),
-                                };
-                            }
-                            ColumnIndex::Bool(index) => {
-                                match index.contains_key(
-                                    &val.parse::<bool>()
-                                        .expect("couldn't parse value to be a boolean"),
-                                ) {
-                                    true => {
-                                        return Err(format!(
-                                            "Error: unique constraint violation for column {}.
-                            Value {} already exists for column {}",
-                                            *name, val, *name
-                                        ));
-                                    }
-                                    false => return Ok(()),
-                                };
-                            }
-                            ColumnIndex::Str(index) => {
-                                match index.contains_key(val) {
-                                    true => {
-                                        return Err(format!(
-                                            "Error: unique constraint violation for column {}.
-                            Value {} already exists for column {}",
-                                            *name, val, *name
-                                        ));
-                                    }
-                                    false => return Ok(()),
-                                };
-                            }
-                            ColumnIndex::None => {
-                                return Err(format!(
-                                    "Error: cannot find index for column {}",
-                                    name
-                                ));
-                            }
-                        };
-                    }
+        for (idx, name) in cols.iter().enumerate() {
+            let c = self.get_column(name.to_string());
+            // Unlike values, NULLs never collide.
+            let val = match (c.is_unique, &values[idx]) {
+                (true, Some(val)) => self.rows.get(name).unwrap().normalize_value(val)?,
+                _ => continue,
+            };
+            if c.index == ColumnIndex::None {
+                return Err(format!("Error: cannot find index for column {}", name));
+            }
+            if !c.index.get_idx_data(&val)?.is_empty() {
+                return Err(c.unique_violation(&val));
+            }
+        }
+        self.check_composite_insert(cols, values)
+    }
+
+    /// Fails when two of the new rows `rows`, holding a value for every column, share a key of
+    /// a unique column or of a unique multi-column index.
+    fn check_unique_batch(&self, rows: &[Vec<Option<String>>]) -> Result<(), String> {
+        for (pos, header) in self.columns.iter().enumerate() {
+            if !header.is_unique {
+                continue;
+            }
+            let mut keys = BTreeSet::new();
+            for val in rows.iter().filter_map(|row| row[pos].as_ref()) {
+                if !keys.insert(IndexKey::parse(&header.datatype, val)?) {
+                    return Err(header.unique_violation(val));
                 }
             }
         }
-        return Ok(());
+        self.check_composite_batch(rows)
     }
 
-    pub fn insert_row(&mut self, cols: &Vec<String>, values: &Vec<Vec<String>>) {
-        for i in 0..cols.len() {
-            let key = &cols[i];
-            let table_col_data = self.rows.get_mut(&key.to_string()).unwrap();
+    /// Appends `values` as new rows, `cols` naming the column each value goes to. Columns left
+    /// out take their default value. Every row is checked against its columns, the unique
+    /// keys already in the table, the other rows of `values` and the CHECK constraints before
+    /// the first one is written, so a failing insert leaves the table as it was.
+    pub fn insert_row(
+        &mut self,
+        cols: &[String],
+        values: &[Vec<Option<String>>],
+    ) -> Result<(), String> {
+        if let Some(col_name) = cols.iter().find(|c| !self.column_exist(c.to_string())) {
+            return Err(format!("column {} does not exist", col_name));
+        }
+        let col_names = self
+            .columns
+            .iter()
+            .map(|c| c.name.to_string())
+            .collect::<Vec<String>>();
 
-            let mut column_headers = self
-                .columns
-                .iter_mut()
-                .filter(|c| c.name == key.to_string())
-                .collect::<Vec<&mut ColumnHeader>>();
-
-            let col_index = column_headers
-                .first_mut()
-                .expect("Couldn't find column to insert row")
-                .get_mut_index();
-
-            for value in values {
-                let val = &value[i];
-                match table_col_data {
-                    ColumnData::Int(c_vec) => {
-                        let val = val.parse::<i32>().unwrap();
-                        c_vec.push(val);
-                        if let ColumnIndex::Int(index) = col_index {
-                            index.insert(val, table_col_data.count() - 1);
-                        }
+        let mut next_auto_value = self.columns.iter().find_map(|c| c.auto_increment);
+        let mut full_rows = vec![];
+        for value in values {
+            if value.len() != cols.len() {
+                return Err(format!(
+                    "expected {} values, found {}",
+                    cols.len(),
+                    value.len()
+                ));
+            }
+            let mut row = vec![];
+            for header in &self.columns {
+                let val = match cols.iter().position(|c| *c == header.name) {
+                    Some(idx) => value[idx].clone(),
+                    None if header.auto_increment.is_some() => None,
+                    None if header.default.is_none() && !header.is_nullable => {
+                        return Err(format!(
+                            "column {} cannot be NULL and has no default value",
+                            header.name
+                        ));
                     }
-                    ColumnData::Float(c_vec) => {
-                        c_vec.push(val.parse::<f32>().unwrap());
+                    None => header.default.clone(),
+                };
+                // An AUTO_INCREMENT column takes its next value in place of NULL, and moves
+                // past any value given explicitly so that it never hands that one out.
+                let val = match (header.auto_increment, val) {
+                    (Some(_), None) => {
+                        let next = next_auto_value.unwrap();
+                        next_auto_value = Some(next.saturating_add(1));
+                        Some(next.to_string())
                     }
-                    ColumnData::Bool(c_vec) => {
-                        let val = val.parse::<bool>().unwrap();
-                        c_vec.push(val);
-                        if let ColumnIndex::Bool(index) = col_index {
-                            index.insert(val, table_col_data.count() - 1);
-                        }
+                    (Some(_), Some(val)) => {
+                        let given = val.parse::<i64>().map_err(|e| e.to_string())?;
+                        next_auto_value = next_auto_value.max(Some(given.saturating_add(1)));
+                        Some(val)
                     }
-                    ColumnData::Str(c_vec) => {
-                        c_vec.push(val.to_string());
-                        if let ColumnIndex::Str(index) = col_index {
-                            index.insert(val.to_string(), table_col_data.count() - 1);
-                        }
+                    (None, val) => val,
+                };
+                let val = match val {
+                    Some(val) => Some(self.rows.get(&header.name).unwrap().normalize_value(&val)?),
+                    None if !header.is_nullable => {
+                        return Err(format!("column {} cannot be NULL", header.name));
                     }
-                    ColumnData::None => panic!("None data Found"),
+                    None => None,
+                };
+                row.push(val);
+            }
+            self.does_violate_unique_constraint(&col_names, &row)?;
+            self.check_row(&row)?;
+            full_rows.push(row);
+        }
+        self.check_unique_batch(&full_rows)?;
+
+        for row in full_rows {
+            for (header, val) in self.columns.iter_mut().zip(row) {
+                let column = self.rows.get_mut(&header.name).unwrap();
+                column.push_value(val.as_deref())?;
+                if let (true, Some(val)) = (header.is_indexed, val) {
+                    header
+                        .get_mut_index()
+                        .insert_value(&val, column.count() - 1)?;
                 }
             }
+            self.insert_composite_keys(self.row_count() - 1)?;
+        }
+        if let Some(header) = self.columns.iter_mut().find(|c| c.auto_increment.is_some()) {
+            header.auto_increment = next_auto_value;
         }
+        Ok(())
     }
 
-    fn select_data(
+    /// Checks that giving `rows` the values in `values` keeps the unique column `col` unique. The
+    /// keys `rows` hold now don't count, they are replaced by the update.
+    fn check_unique_update(
         &self,
-        columns_to_fetch: &Vec<String>,
-        indexes: &Vec<usize>,
-    ) -> Vec<Vec<String>> {
-        let mut data = vec![];
-        for col in columns_to_fetch {
-            let row = self.rows.get(col).unwrap();
-            data.push(row.get_serialized_col_data_by_index(indexes));
+        col: &ColumnHeader,
+        rows: &[usize],
+        values: &[Option<String>],
+    ) -> Result<(), String> {
+        let updated_rows = rows.iter().collect::<HashSet<&usize>>();
+        let mut new_keys = HashSet::new();
+        for val in values.iter().flatten() {
+            let is_taken = col
+                .index
+                .get_idx_data(val)?
+                .iter()
+                .any(|row| !updated_rows.contains(row));
+            if is_taken || !new_keys.insert(val) {
+                return Err(col.unique_violation(val));
+            }
         }
-        return data;
+        Ok(())
     }
 
-    fn execute_select_query_without_index(&self, sq: &SelectQuery) -> Vec<Vec<String>> {
-        let expr = sq.where_expressions.first();
-        match expr {
-            Some(where_expr) => {
-                let col = self.get_column(where_expr.left.to_string());
-                let row = self.rows.get(&col.name).unwrap();
-
-                let indices = row.get_serialized_col_data_by_scanning(&where_expr);
-
-                let projected_data = sq
-                    .projection
-                    .iter()
-                    .map(|col_name| {
-                        self.rows
-                            .get(&col_name.to_string())
-                            .expect("The searched column doestn't exist")
-                    })
-                    .map(|col_data| col_data.get_serialized_col_data_by_index(&indices))
-                    .collect::<Vec<Vec<String>>>();
-
-                return projected_data;
+    /// Computes `scalar` against the values `row` holds, `None` standing for NULL. Arithmetic
+    /// on NULL gives NULL.
+    fn evaluate_scalar(&self, scalar: &Scalar, row: usize) -> Result<Option<String>, String> {
+        match scalar {
+            Scalar::Column(name) => match self.rows.get(name) {
+                Some(column) => Ok(column.get_value(row)),
+                None => Err(format!(
+                    "cannot find column {} in table {}",
+                    name, self.name
+                )),
+            },
+            Scalar::Literal(value) => Ok(Some(value.to_string())),
+            Scalar::Null => Ok(None),
+            Scalar::Arithmetic(left, op, right) => {
+                match (
+                    self.evaluate_scalar(left, row)?,
+                    self.evaluate_scalar(right, row)?,
+                ) {
+                    (Some(left), Some(right)) => Ok(Some(util::arithmetic(&left, op, &right)?)),
+                    _ => Ok(None),
+                }
+            }
+        }
+    }
+
+    /// Applies an UPDATE and returns the number of rows it changed. Nothing is written unless
+    /// every new value fits its column and keeps the unique columns unique.
+    pub fn update_rows(&mut self, uq: &UpdateQuery) -> Result<usize, String> {
+        let (rows, new_values) = self.evaluate_update(uq)?;
+        self.write_values(&rows, &uq.columns(), &new_values)?;
+        Ok(rows.len())
+    }
+
+    /// The rows an UPDATE changes along with, for each assignment, the values they get.
+    pub fn evaluate_update(&self, uq: &UpdateQuery) -> Result<(Vec<usize>, NewValues), String> {
+        for assignment in &uq.assignments {
+            if !self.column_exist(assignment.column.to_string()) {
+                return Err(format!(
+                    "cannot find column {} in table {}",
+                    assignment.column, self.name
+                ));
+            }
+        }
+
+        let rows = self.find_rows(uq.where_clause.as_ref())?;
+
+        // Every SET expression sees the rows as they were before the update.
+        let mut new_values: Vec<Vec<Option<String>>> = vec![];
+        for assignment in &uq.assignments {
+            let column = self.rows.get(&assignment.column).unwrap();
+            let is_nullable = self.get_column(assignment.column.to_string()).is_nullable;
+            let mut values = vec![];
+            for row in &rows {
+                let value = match self.evaluate_scalar(&assignment.value, *row)? {
+                    Some(value) => Some(column.normalize_value(&value)?),
+                    None if is_nullable => None,
+                    None => return Err(format!("column {} cannot be NULL", assignment.column)),
+                };
+                values.push(value);
+            }
+            new_values.push(values);
+        }
+        Ok((rows, new_values))
+    }
+
+    /// Gives `rows` the values in `new_values`, one list of values per column in `columns`.
+    /// Nothing is written unless the new values keep the constraints of the table.
+    pub fn write_values(
+        &mut self,
+        rows: &[usize],
+        columns: &[String],
+        new_values: &[Vec<Option<String>>],
+    ) -> Result<(), String> {
+        for (col_name, values) in columns.iter().zip(new_values) {
+            let col = self.get_column(col_name.to_string());
+            if col.is_unique {
+                self.check_unique_update(col, rows, values)?;
+            }
+        }
+        self.check_updated_rows(rows, columns, new_values)?;
+        let composite_keys = self.composite_keys_after_update(rows, columns, new_values)?;
+        self.update_composite_keys(rows, composite_keys)?;
+
+        for (col_name, values) in columns.iter().zip(new_values) {
+            let column = self.rows.get_mut(col_name).unwrap();
+            let header = self
+                .columns
+                .iter_mut()
+                .find(|c| c.name == *col_name)
+                .unwrap();
+            // All the old keys go before any new one is added, otherwise a new key could be
+            // dropped again as the old key of a row updated later on.
+            if header.is_indexed {
+                for row in rows {
+                    if let Some(old) = column.get_value(*row) {
+                        header.index.remove_value(&old, *row)?;
+                    }
+                }
+            }
+            for (row, value) in rows.iter().zip(values) {
+                column.set_value(*row, value.as_deref())?;
+                if let (true, Some(value)) = (header.is_indexed, value) {
+                    header.index.insert_value(value, *row)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the index `ciq` asks for over the rows already in the table.
+    pub fn create_index(&mut self, ciq: &CreateIndexQuery) -> Result<(), String> {
+        self.add_index(&ciq.name, &ciq.columns, ciq.is_unique)
+    }
+
+    /// Builds an index called `name` over `columns`. An index over a single column is kept on
+    /// the column, one over several columns goes to `indexes`.
+    fn add_index(&mut self, name: &str, columns: &[String], is_unique: bool) -> Result<(), String> {
+        if let Some(col_name) = columns.iter().find(|c| !self.column_exist(c.to_string())) {
+            return Err(format!(
+                "cannot find column {} in table {}",
+                col_name, self.name
+            ));
+        }
+        if let [col_name, ..] = columns {
+            if columns.iter().skip(1).any(|c| c == col_name) {
+                return Err(format!("column {} is indexed twice", col_name));
+            }
+        }
+        let col_name = match columns {
+            [] => return Err(format!("index {} covers no column", name)),
+            [col_name] => col_name,
+            _ => {
+                if self.indexes.iter().any(|index| index.columns == columns) {
+                    return Err(format!(
+                        "columns ({}) are already indexed",
+                        columns.join(", ")
+                    ));
+                }
+                let index = self.create_composite_index(name, columns, is_unique)?;
+                self.indexes.push(index);
+                return Ok(());
+            }
+        };
+
+        let header = self.get_column(col_name.to_string());
+        if header.is_indexed {
+            return Err(format!("column {} is already indexed", col_name));
+        }
+        let mut index = ColumnIndex::new(&header.datatype);
+        if index == ColumnIndex::None {
+            return Err(format!(
+                "cannot index column {} of type {}",
+                col_name, header.datatype
+            ));
+        }
+
+        let column = self.rows.get(col_name).unwrap();
+        for row in 0..column.count() {
+            if let Some(val) = column.get_value(row) {
+                index.insert_value(&val, row)?;
+                if is_unique && index.get_idx_data(&val)?.len() > 1 {
+                    return Err(format!(
+                        "Error: unique constraint violation for index {}. Value {} already exists for column {}",
+                        name, val, col_name
+                    ));
+                }
+            }
+        }
+
+        let header = self
+            .columns
+            .iter_mut()
+            .find(|c| c.name == *col_name)
+            .unwrap();
+        header.index = index;
+        header.is_indexed = true;
+        header.is_unique = is_unique;
+        header.index_name = Some(name.to_string());
+        Ok(())
+    }
+
+    pub fn has_index(&self, name: &str) -> bool {
+        self.columns
+            .iter()
+            .any(|c| c.index_name.as_deref() == Some(name))
+            || self
+                .indexes
+                .iter()
+                .any(|index| !index.is_primary && index.name == name)
+    }
+
+    /// Drops the index called `name`, its columns keep their values.
+    pub fn drop_index(&mut self, name: &str) -> Result<(), String> {
+        if let Some(pos) = self
+            .indexes
+            .iter()
+            .position(|index| !index.is_primary && index.name == name)
+        {
+            self.indexes.remove(pos);
+            return Ok(());
+        }
+        match self
+            .columns
+            .iter_mut()
+            .find(|c| c.index_name.as_deref() == Some(name))
+        {
+            Some(header) => {
+                header.index = ColumnIndex::new(&header.datatype);
+                header.is_indexed = false;
+                header.is_unique = false;
+                header.index_name = None;
+                Ok(())
+            }
+            None => Err(format!("cannot find index {} on table {}", name, self.name)),
+        }
+    }
+
+    /// Applies a DELETE and returns the number of rows it removed.
+    pub fn delete_rows(&mut self, dq: &DeleteQuery) -> Result<usize, String> {
+        let deleted = self.find_rows(dq.where_clause.as_ref())?;
+        self.remove_rows(&deleted);
+        Ok(deleted.len())
+    }
+
+    /// Removes the rows at the sorted positions in `deleted`.
+    pub fn remove_rows(&mut self, deleted: &[usize]) {
+        for column in self.rows.values_mut() {
+            column.remove_rows(deleted);
+        }
+        for header in &mut self.columns {
+            header.index.remove_rows(deleted);
+        }
+        for index in &mut self.indexes {
+            index.remove_rows(deleted);
+        }
+    }
+
+    /// Deletes every row and starts the AUTO_INCREMENT column over, giving the number of rows
+    /// deleted.
+    pub fn truncate(&mut self) -> usize {
+        let count = self.row_count();
+        self.remove_rows(&(0..count).collect::<Vec<usize>>());
+        let start = self.auto_increment_start;
+        if let Some(header) = self.columns.iter_mut().find(|c| c.auto_increment.is_some()) {
+            header.auto_increment = Some(start);
+        }
+        count
+    }
+
+    /// Sorted positions of the rows matching `where_clause`, all of them without one.
+    pub fn find_rows(&self, where_clause: Option<&Predicate>) -> Result<Vec<usize>, String> {
+        match where_clause {
+            Some(predicate) => self.evaluate_predicate(predicate, None),
+            None => Ok((0..self.row_count()).collect()),
+        }
+    }
+
+    /// The value `row` holds in the column `col_name`, `None` for NULL.
+    pub fn get_value(&self, col_name: &str, row: usize) -> Option<String> {
+        self.rows.get(col_name).unwrap().get_value(row)
+    }
+
+    pub fn row_count(&self) -> usize {
+        match self.columns.first() {
+            Some(col) => self.rows.get(&col.name).map_or(0, |c| c.count()),
+            None => 0,
+        }
+    }
+
+    /// True when the predicate can be answered without scanning the table.
+    fn is_index_lookup(&self, predicate: &Predicate) -> bool {
+        match predicate {
+            Predicate::And(left, right) => {
+                self.is_index_lookup(left) || self.is_index_lookup(right)
+            }
+            Predicate::Or(left, right) => self.is_index_lookup(left) && self.is_index_lookup(right),
+            Predicate::Not(inner) => self.is_index_lookup(&inner.negate()),
+            Predicate::Expression(expr) => {
+                self.column_exist(expr.left.to_string()) && {
+                    let col = self.get_column(expr.left.to_string());
+                    (col.is_indexed && col.index != ColumnIndex::None)
+                        || self
+                            .indexes
+                            .iter()
+                            .any(|index| index.columns[0] == col.name)
+                }
             }
+            Predicate::IsNull(_) | Predicate::IsNotNull(_) => false,
+        }
+    }
+
+    /// Returns the sorted positions of the rows matching `expr`. When `rows` is given only
+    /// those positions are considered, otherwise the whole table is.
+    fn evaluate_expression(
+        &self,
+        expr: &Expression,
+        rows: Option<&[usize]>,
+    ) -> Result<Vec<usize>, String> {
+        if expr.aggregate.is_some() {
+            return Err(format!(
+                "{} is an aggregate, aggregates can only be filtered on in HAVING",
+                expr.left
+            ));
+        }
+        if !self.column_exist(expr.left.to_string()) {
+            return Err(format!(
+                "cannot find column {} in table {}",
+                expr.left, self.name
+            ));
+        }
+
+        let col = self.get_column(expr.left.to_string());
+        let Operator::Binary(op) = &expr.op;
+        if col.is_indexed && col.index != ColumnIndex::None {
+            let mut matched = match op {
+                Binary::Eq => col.index.get_idx_data(&expr.right)?.to_vec(),
+                _ => col.index.get_idx_data_by_range(&expr.right, op)?,
+            };
+            matched.sort_unstable();
+            return Ok(match rows {
+                Some(rows) => util::intersect(&matched, rows),
+                None => matched,
+            });
+        }
 
+        let column = self.rows.get(&col.name).unwrap();
+        match rows {
+            Some(rows) => column.get_serialized_col_data_by_scanning(expr, rows),
             None => {
-                panic!("Invalid where expression given");
-            }
-        }
-    }
-
-    pub fn execute_select_query(&self, sq: &SelectQuery) {
-        let mut data: Vec<Vec<String>> = vec![];
-
-        let expr = sq.where_expressions.first();
-        match expr {
-            Some(where_expr) => {
-                let col = self.get_column(where_expr.left.to_string());
-                // println!("Fetched column = {}", col.name);
-                // println!("Is column indexed = {} ", col.is_indexed);
-
-                if col.is_indexed {
-                    match &where_expr.op {
-                        Operator::Binary(bop) => {
-                            match bop {
-                                Binary::Eq => {
-                                    match &col.index.get_idx_data(&where_expr.right) {
-                                        Ok(v) => {
-                                            let matched_indexes = match v {
-                                                Some(idx) => vec![**idx],
-                                                None => vec![],
-                                            };
-                                            data =
-                                                self.select_data(&sq.projection, &matched_indexes);
-                                        }
-                                        Err(e) => {
-                                            println!("Error while trying to retrieve value from index: {}", e);
-                                        }
-                                    }
-                                }
-                                Binary::Gt => {
-                                    match &col
-                                        .index
-                                        .get_idx_data_by_range(&where_expr.right, Binary::Gt)
-                                    {
-                                        Ok(indexes) => {
-                                            data = self.select_data(&sq.projection, indexes);
-                                        }
-                                        Err(e) => {
-                                            println!("Error while trying to retrieve value from index: {}", e);
-                                        }
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
+                let all_rows = (0..column.count()).collect::<Vec<usize>>();
+                column.get_serialized_col_data_by_scanning(expr, &all_rows)
+            }
+        }
+    }
+
+    /// Returns the sorted positions of the rows matching `predicate`, restricted to `rows`
+    /// when given.
+    fn evaluate_predicate(
+        &self,
+        predicate: &Predicate,
+        rows: Option<&[usize]>,
+    ) -> Result<Vec<usize>, String> {
+        if let Predicate::And(..) | Predicate::Expression(_) = predicate {
+            let conjuncts = composite::conjuncts(predicate);
+            if let Some((matched, used)) = self.composite_index_lookup(&conjuncts)? {
+                let mut matched = match rows {
+                    Some(rows) => util::intersect(&matched, rows),
+                    None => matched,
+                };
+                for (i, conjunct) in conjuncts.iter().enumerate() {
+                    if !used.contains(&i) {
+                        matched = self.evaluate_predicate(conjunct, Some(&matched))?;
                     }
+                }
+                return Ok(matched);
+            }
+        }
+        match predicate {
+            Predicate::And(left, right) => {
+                // Let the side that can use an index narrow the rows down first, the other
+                // side then only has to scan what is left.
+                let (first, second) = if !self.is_index_lookup(left) && self.is_index_lookup(right)
+                {
+                    (right, left)
                 } else {
-                    println!("Executing select expression wihtout index");
-                    data = self.execute_select_query_without_index(&sq);
+                    (left, right)
+                };
+                let matched = self.evaluate_predicate(first, rows)?;
+                self.evaluate_predicate(second, Some(&matched))
+            }
+            Predicate::Or(left, right) => Ok(util::union(
+                &self.evaluate_predicate(left, rows)?,
+                &self.evaluate_predicate(right, rows)?,
+            )),
+            Predicate::Not(inner) => self.evaluate_predicate(&inner.negate(), rows),
+            Predicate::Expression(expr) => self.evaluate_expression(expr, rows),
+            Predicate::IsNull(col_name) | Predicate::IsNotNull(col_name) => {
+                let column = match self.rows.get(col_name) {
+                    Some(column) => column,
+                    None => {
+                        return Err(format!(
+                            "cannot find column {} in table {}",
+                            col_name, self.name
+                        ))
+                    }
+                };
+                let wants_null = matches!(predicate, Predicate::IsNull(_));
+                let candidates = match rows {
+                    Some(rows) => rows.to_vec(),
+                    None => (0..self.row_count()).collect(),
+                };
+                Ok(candidates
+                    .into_iter()
+                    .filter(|row| column.is_null(*row) == wants_null)
+                    .collect())
+            }
+        }
+    }
+
+    /// Puts the matched rows in ORDER BY order. When the leading key has an index of its own
+    /// the rows are read off the index instead of being sorted.
+    fn sort_rows(&self, indexes: Vec<usize>, order_by: &[OrderBy]) -> Result<Vec<usize>, String> {
+        let first_key = match order_by.first() {
+            Some(key) => key,
+            None => return Ok(indexes),
+        };
+        for key in order_by {
+            if !self.column_exist(key.column.to_string()) {
+                return Err(format!(
+                    "cannot find column {} in table {}",
+                    key.column, self.name
+                ));
+            }
+        }
+
+        // An index on the first key hands out its rows already in order, the other keys only
+        // break ties within one of its keys. NULLs are never indexed and go to one end.
+        let col = self.get_column(first_key.column.to_string());
+        if col.is_indexed && col.index != ColumnIndex::None {
+            let mut is_matched = vec![false; self.row_count()];
+            for idx in &indexes {
+                is_matched[*idx] = true;
+            }
+            let mut groups = col.index.get_row_groups_in_order();
+            if !first_key.asc {
+                groups.reverse();
+            }
+            let mut sorted = vec![];
+            for group in groups {
+                let mut rows = group
+                    .iter()
+                    .copied()
+                    .filter(|idx| is_matched[*idx])
+                    .collect::<Vec<usize>>();
+                rows.sort_unstable();
+                sorted.extend(self.sort_by_keys(rows, &order_by[1..]));
+            }
+            let column = self.rows.get(&col.name).unwrap();
+            let nulls = indexes
+                .into_iter()
+                .filter(|idx| column.is_null(*idx))
+                .collect::<Vec<usize>>();
+            let nulls = self.sort_by_keys(nulls, &order_by[1..]);
+            return Ok(match first_key.nulls_first {
+                true => nulls.into_iter().chain(sorted).collect(),
+                false => sorted.into_iter().chain(nulls).collect(),
+            });
+        }
+        Ok(self.sort_by_keys(indexes, order_by))
+    }
+
+    /// `rows` ordered by `order_by`, rows with equal keys keeping their order.
+    fn sort_by_keys(&self, mut rows: Vec<usize>, order_by: &[OrderBy]) -> Vec<usize> {
+        let keys = order_by
+            .iter()
+            .map(|key| (self.rows.get(&key.column).unwrap(), key))
+            .collect::<Vec<(&Column, &OrderBy)>>();
+        rows.sort_by(|a, b| {
+            for (column, key) in &keys {
+                let ordering = column.compare_rows_by_key(*a, *b, key);
+                if ordering != Ordering::Equal {
+                    return ordering;
                 }
             }
+            Ordering::Equal
+        });
+        rows
+    }
+
+    /// Rows matching the where clause in table order. With `wanted` set it stops once that
+    /// many were found, scanning the table batch by batch instead of all at once.
+    fn filter_rows(
+        &self,
+        where_clause: Option<&Predicate>,
+        wanted: Option<usize>,
+    ) -> Result<Vec<usize>, String> {
+        let row_count = self.row_count();
+        let (predicate, wanted) = match (where_clause, wanted) {
+            (None, None) => return Ok((0..row_count).collect()),
+            (None, Some(wanted)) => return Ok((0..row_count.min(wanted)).collect()),
+            (Some(predicate), None) => return self.evaluate_predicate(predicate, None),
+            (Some(predicate), Some(wanted)) => (predicate, wanted),
+        };
+
+        if self.is_index_lookup(predicate) {
+            let mut matched = self.evaluate_predicate(predicate, None)?;
+            matched.truncate(wanted);
+            return Ok(matched);
+        }
+
+        let mut matched = vec![];
+        let mut start = 0;
+        while start < row_count && matched.len() < wanted {
+            let end = row_count.min(start + SCAN_BATCH_SIZE);
+            let batch = (start..end).collect::<Vec<usize>>();
+            matched.extend(self.evaluate_predicate(predicate, Some(&batch))?);
+            start = end;
+        }
+        matched.truncate(wanted);
+        Ok(matched)
+    }
+
+    /// Positions of the rows a select query returns, in the order they are returned.
+    pub fn get_matching_rows(&self, sq: &SelectQuery) -> Result<Vec<usize>, String> {
+        // Without ORDER BY the first matches are the ones returned, so there is no need to
+        // look any further once OFFSET + LIMIT of them were found.
+        let wanted = match sq.limit {
+            Some(limit) if sq.order_by.is_empty() => Some(sq.offset.saturating_add(limit)),
+            _ => None,
+        };
+        let indexes = self.filter_rows(sq.where_clause.as_ref(), wanted)?;
+        let indexes = self.sort_rows(indexes, &sq.order_by)?;
+
+        Ok(indexes
+            .into_iter()
+            .skip(sq.offset)
+            .take(sq.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Computes `aggregate` over `rows`. Like in SQL, NULLs are skipped and every aggregate
+    /// but COUNT gives NULL (`None`) when there is nothing left to aggregate.
+    fn compute_aggregate(
+        &self,
+        aggregate: &Aggregate,
+        rows: &[usize],
+    ) -> Result<Option<String>, String> {
+        let col_name = match &aggregate.column {
+            Some(col_name) => col_name,
+            None => return Ok(Some(rows.len().to_string())),
+        };
+        let column = match self.rows.get(col_name) {
+            Some(column) => column,
             None => {
-                println!("In none block");
-                for col in &sq.projection {
-                    let row = self.rows.get(col).unwrap();
-                    let column = row.get_serialized_col_data();
-                    data.push(column);
+                return Err(format!(
+                    "cannot find column {} in table {}",
+                    col_name, self.name
+                ))
+            }
+        };
+        let rows = rows
+            .iter()
+            .copied()
+            .filter(|idx| !column.is_null(*idx))
+            .collect::<Vec<usize>>();
+        if rows.is_empty() && aggregate.function != AggregateFunction::Count {
+            return Ok(None);
+        }
+
+        match aggregate.function {
+            AggregateFunction::Count => Ok(Some(rows.len().to_string())),
+            AggregateFunction::Sum | AggregateFunction::Avg => {
+                if let ColumnData::Decimal(cd, decimal) = &column.data {
+                    // Summed exactly, a DECIMAL never goes through floating point.
+                    let sum = rows
+                        .iter()
+                        .try_fold(0i128, |sum, idx| sum.checked_add(cd[*idx]))
+                        .ok_or_else(|| format!("{} is out of range", aggregate))?;
+                    return Ok(Some(match aggregate.function {
+                        AggregateFunction::Avg => {
+                            decimal.format_average(sum, rows.len() as i128)?
+                        }
+                        _ => decimal.format(sum),
+                    }));
+                }
+                let sum = match column.sum(&rows) {
+                    Some(sum) => sum,
+                    None => {
+                        return Err(format!(
+                            "cannot compute {}, {} isn't numeric",
+                            aggregate, col_name
+                        ))
+                    }
+                };
+                let count = rows.len() as f64;
+                Ok(Some(match (aggregate.function, sum) {
+                    (AggregateFunction::Avg, Sum::Integer(sum)) => (sum as f64 / count).to_string(),
+                    (AggregateFunction::Avg, Sum::Float(sum)) => (sum / count).to_string(),
+                    // The SUM of an integer column is a BIGINT.
+                    (_, Sum::Integer(sum)) => i64::try_from(sum)
+                        .map_err(|_| format!("{} is out of range", aggregate))?
+                        .to_string(),
+                    // Floats are stored as f32, more digits than that would only be noise.
+                    (_, Sum::Float(sum)) => (sum as f32).to_string(),
+                }))
+            }
+            AggregateFunction::Min | AggregateFunction::Max => {
+                let from_end = aggregate.function == AggregateFunction::Max;
+                let col = self.get_column(col_name.to_string());
+                let row = if col.is_indexed && col.index != ColumnIndex::None {
+                    // The answer sits at one end of the index, the first key there that
+                    // belongs to a matched row.
+                    if rows.len() == self.row_count() {
+                        col.index.find_first_row(from_end, |_| true)
+                    } else {
+                        let mut is_matched = vec![false; self.row_count()];
+                        for idx in &rows {
+                            is_matched[*idx] = true;
+                        }
+                        col.index.find_first_row(from_end, |idx| is_matched[idx])
+                    }
+                } else if from_end {
+                    rows.iter()
+                        .copied()
+                        .max_by(|a, b| column.compare_rows(*a, *b))
+                } else {
+                    rows.iter()
+                        .copied()
+                        .min_by(|a, b| column.compare_rows(*a, *b))
+                };
+                Ok(row.map(|row| column.get_serialized_value(row)))
+            }
+        }
+    }
+
+    /// The values of the projected columns for the rows `sq` selects, `None` standing for
+    /// NULL.
+    fn get_projected_values(&self, sq: &SelectQuery) -> Result<NewValues, String> {
+        if !is_aggregate_query(sq) {
+            let indexes = self.get_matching_rows(sq)?;
+            return Ok(projected_columns(sq)
+                .iter()
+                .map(|col| {
+                    indexes
+                        .iter()
+                        .map(|row| self.get_value(col, *row))
+                        .collect()
+                })
+                .collect());
+        }
+        self.aggregate_values(sq)
+    }
+
+    /// The values of a select query with aggregates, GROUP BY or HAVING.
+    fn aggregate_values(&self, sq: &SelectQuery) -> Result<NewValues, String> {
+        // Without GROUP BY the aggregates fold every matched row into a single result row.
+        let rows = self.filter_rows(sq.where_clause.as_ref(), None)?;
+        let mut groups = match sq.group_by.is_empty() {
+            true => vec![rows],
+            false => self.group_rows(&rows, &sq.group_by)?,
+        };
+        if let Some(having) = &sq.having {
+            let mut kept = vec![];
+            for group in groups {
+                if self.evaluate_having(having, &group, &sq.group_by)? {
+                    kept.push(group);
+                }
+            }
+            groups = kept;
+        }
+        if !sq.group_by.is_empty() {
+            // Every row of a group holds the same key, so the groups sort like their first rows.
+            let first_rows = groups.iter().map(|g| g[0]).collect::<Vec<usize>>();
+            let mut groups_by_first_row = groups
+                .into_iter()
+                .map(|g| (g[0], g))
+                .collect::<HashMap<usize, Vec<usize>>>();
+            groups = self
+                .sort_rows(first_rows, &sq.order_by)?
+                .into_iter()
+                .map(|row| groups_by_first_row.remove(&row).unwrap())
+                .collect();
+        }
+        let groups = groups
+            .into_iter()
+            .skip(sq.offset)
+            .take(sq.limit.unwrap_or(usize::MAX))
+            .collect::<Vec<Vec<usize>>>();
+
+        let mut data = vec![];
+        for p in &sq.projection {
+            let mut values = vec![];
+            for group in &groups {
+                let value = match p {
+                    Projection::Aggregate(aggregate) => self.compute_aggregate(aggregate, group)?,
+                    Projection::Column(col_name) => {
+                        self.get_group_key(col_name, group, &sq.group_by)?
+                    }
+                };
+                values.push(value);
+            }
+            data.push(values);
+        }
+        Ok(data)
+    }
+
+    /// Runs the select query `sq` and returns its result along with the type of each column.
+    pub fn select_result(&self, sq: &SelectQuery) -> Result<QueryResult, String> {
+        let data = self.get_projected_values(sq)?;
+        let mut columns = vec![];
+        for p in &sq.projection {
+            let datatype = match p {
+                Projection::Column(col_name) => {
+                    self.get_column(col_name.to_string()).datatype.clone()
+                }
+                Projection::Aggregate(aggregate) => self.aggregate_type(aggregate)?,
+            };
+            columns.push((p.to_string(), datatype));
+        }
+        Ok(QueryResult { columns, data })
+    }
+
+    /// The type of the values `compute_aggregate` gives for `aggregate`.
+    fn aggregate_type(&self, aggregate: &Aggregate) -> Result<DataType, String> {
+        let datatype = match &aggregate.column {
+            Some(col_name) if self.column_exist(col_name.to_string()) => {
+                &self.get_column(col_name.to_string()).datatype
+            }
+            Some(col_name) => {
+                return Err(format!(
+                    "cannot find column {} in table {}",
+                    col_name, self.name
+                ))
+            }
+            None => &DataType::BigInt,
+        };
+        Ok(match (aggregate.function, datatype) {
+            (AggregateFunction::Count, _) => DataType::BigInt,
+            (AggregateFunction::Min | AggregateFunction::Max, datatype) => datatype.clone(),
+            (AggregateFunction::Sum, DataType::Decimal(decimal)) => DataType::Decimal(Decimal {
+                precision: Decimal::MAX_PRECISION,
+                scale: decimal.scale,
+            }),
+            (AggregateFunction::Avg, DataType::Decimal(decimal)) => {
+                DataType::Decimal(decimal.average_type())
+            }
+            (AggregateFunction::Sum, datatype) if datatype.is_integer() => DataType::BigInt,
+            _ => DataType::Float,
+        })
+    }
+
+    /// Splits `rows` into groups sharing the same values in the `group_by` columns. Groups
+    /// come in the order their first row appears in.
+    fn group_rows(&self, rows: &[usize], group_by: &[String]) -> Result<Vec<Vec<usize>>, String> {
+        let mut key_columns = vec![];
+        for col_name in group_by {
+            match self.rows.get(col_name) {
+                Some(column) => key_columns.push(column),
+                None => {
+                    return Err(format!(
+                        "cannot find column {} in table {}",
+                        col_name, self.name
+                    ))
+                }
+            }
+        }
+
+        let mut groups: Vec<Vec<usize>> = vec![];
+        let mut group_of_key: HashMap<Vec<Option<String>>, usize> = HashMap::new();
+        for row in rows {
+            let key = key_columns
+                .iter()
+                .map(|column| column.get_value(*row))
+                .collect::<Vec<Option<String>>>();
+            match group_of_key.get(&key) {
+                Some(group) => groups[*group].push(*row),
+                None => {
+                    group_of_key.insert(key, groups.len());
+                    groups.push(vec![*row]);
                 }
             }
         }
+        Ok(groups)
+    }
+
+    /// Value of a GROUP BY column for a group, read off its first row. `None` is NULL.
+    fn get_group_key(
+        &self,
+        col_name: &str,
+        group: &[usize],
+        group_by: &[String],
+    ) -> Result<Option<String>, String> {
+        if !group_by.iter().any(|c| c == col_name) {
+            return Err(format!(
+                "column {} must appear in GROUP BY or be used inside an aggregate function",
+                col_name
+            ));
+        }
+        Ok(self.rows.get(col_name).unwrap().get_value(group[0]))
+    }
 
-        let rotated_data = util::rotate_2d_vec(&data);
-        util::pretty_print(&rotated_data, &sq.projection);
+    /// Whether a group passes the HAVING clause. A comparison against NULL never holds.
+    fn evaluate_having(
+        &self,
+        predicate: &Predicate,
+        group: &[usize],
+        group_by: &[String],
+    ) -> Result<bool, String> {
+        match predicate {
+            Predicate::And(left, right) => Ok(self.evaluate_having(left, group, group_by)?
+                && self.evaluate_having(right, group, group_by)?),
+            Predicate::Or(left, right) => Ok(self.evaluate_having(left, group, group_by)?
+                || self.evaluate_having(right, group, group_by)?),
+            Predicate::Not(inner) => self.evaluate_having(&inner.negate(), group, group_by),
+            Predicate::Expression(expr) => {
+                let value = match &expr.aggregate {
+                    Some(aggregate) => self.compute_aggregate(aggregate, group)?,
+                    None => self.get_group_key(&expr.left, group, group_by)?,
+                };
+                let Operator::Binary(op) = &expr.op;
+                Ok(match value {
+                    Some(value) => util::compare(&value, op, &expr.right),
+                    None => false,
+                })
+            }
+            Predicate::IsNull(col_name) => {
+                Ok(self.get_group_key(col_name, group, group_by)?.is_none())
+            }
+            Predicate::IsNotNull(col_name) => {
+                Ok(self.get_group_key(col_name, group, group_by)?.is_some())
+            }
+        }
     }
 
     pub fn print_table(&self) {
         let mut table = PTable::new();
-        table.add_row(row!["Column Name", "Data Type"]);
+        table.add_row(row!["Column Name", "Data Type", "Index", "Constraints"]);
 
         for col in &self.columns {
-            table.add_row(row![col.name, col.datatype]);
+            let index = match (&col.index_name, col.is_primary_key) {
+                (Some(name), _) if col.is_unique => format!("{} (unique)", name),
+                (Some(name), _) => name.to_string(),
+                (None, true) => "PRIMARY".to_string(),
+                (None, false) => String::new(),
+            };
+            let mut indexes = vec![index];
+            for index in self
+                .indexes
+                .iter()
+                .filter(|i| i.columns.contains(&col.name))
+            {
+                let columns = index.columns.join(", ");
+                indexes.push(match index.is_unique && !index.is_primary {
+                    true => format!("{} (unique) ({})", index.name, columns),
+                    false => format!("{} ({})", index.name, columns),
+                });
+            }
+            indexes.retain(|index| !index.is_empty());
+            let mut constraints = vec![];
+            if !col.is_nullable {
+                constraints.push("NOT NULL".to_string());
+            }
+            if let Some(next) = col.auto_increment {
+                constraints.push(format!("AUTO_INCREMENT (next {})", next));
+            }
+            for check in self.checks.iter().filter(|c| c.covers(&col.name)) {
+                constraints.push(format!("CHECK {} ({})", check.name, check.expr));
+            }
+            for fk in self.foreign_keys.iter().filter(|fk| fk.column == col.name) {
+                constraints.push(format!(
+                    "FOREIGN KEY {} REFERENCES {} ({})",
+                    fk.name, fk.parent_table, fk.parent_column
+                ));
+            }
+            table.add_row(row![
+                col.name,
+                col.datatype,
+                indexes.join("\n"),
+                constraints.join("\n")
+            ]);
         }
 
         table.printstd();
@@ -633,7 +1969,7 @@ impl Table {
         let header_row = Row::new(
             cnames
                 .iter()
-                .map(|col| Cell::new(&col))
+                .map(|col| Cell::new(col))
                 .collect::<Vec<Cell>>(),
         );
 
@@ -668,20 +2004,86 @@ impl Table {
     // pub fn does_column_value_match(&self, _column: String, _value: String) {}
 }
 
-#[cfg(test)]
+fn is_aggregate_query(sq: &SelectQuery) -> bool {
+    sq.projection
+        .iter()
+        .any(|p| matches!(p, Projection::Aggregate(_)))
+        || !sq.group_by.is_empty()
+        || sq.having.is_some()
+}
+
+fn projected_columns(sq: &SelectQuery) -> Vec<String> {
+    sq.projection.iter().map(|p| p.to_string()).collect()
+}
 
+#[cfg(test)]
 mod tests {
     use super::*;
     use sqlparser::{ast::Statement, dialect::MySqlDialect, parser::Parser};
+    fn create_table(command: &str) -> Table {
+        let dialect = MySqlDialect {};
+        let statement = &Parser::parse_sql(&dialect, command).unwrap()[0];
+        Table::new(CreateQuery::new(statement).unwrap()).unwrap()
+    }
+
+    fn projected_data(table: &Table, query: &str) -> Result<Vec<Vec<String>>, String> {
+        table
+            .select_result(&select_query(query))
+            .map(|result| result.display_data())
+    }
+
+    fn select_query(query: &str) -> SelectQuery {
+        let dialect = MySqlDialect {};
+        let statement = &Parser::parse_sql(&dialect, query).unwrap()[0];
+        SelectQuery::new(statement).unwrap()
+    }
+
+    fn where_clause(query: &str) -> Predicate {
+        select_query(query).where_clause.unwrap()
+    }
+
+    fn update_query(query: &str) -> UpdateQuery {
+        let dialect = MySqlDialect {};
+        let statement = &Parser::parse_sql(&dialect, query).unwrap()[0];
+        UpdateQuery::new(statement).unwrap()
+    }
+
+    fn delete_query(query: &str) -> DeleteQuery {
+        let dialect = MySqlDialect {};
+        let statement = &Parser::parse_sql(&dialect, query).unwrap()[0];
+        DeleteQuery::new(statement).unwrap()
+    }
+
+    fn create_index_query(query: &str) -> CreateIndexQuery {
+        let dialect = MySqlDialect {};
+        let statement = &Parser::parse_sql(&dialect, query).unwrap()[0];
+        CreateIndexQuery::new(statement).unwrap()
+    }
+
+    fn to_strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    /// Row values to insert, `NULL` standing for NULL.
+    fn to_values(values: &[&str]) -> Vec<Option<String>> {
+        values
+            .iter()
+            .map(|v| match *v {
+                "NULL" => None,
+                v => Some(v.to_string()),
+            })
+            .collect()
+    }
+
     #[test]
     fn tests_creating_a_table() {
         let command =
             String::from("CREATE TABLE users (id int, name string, bounty float, unknown unknown)");
         let dialect = MySqlDialect {};
-        let statement = &Parser::parse_sql(&dialect, command.to_string()).unwrap()[0];
+        let statement = &Parser::parse_sql(&dialect, &command).unwrap()[0];
         if let Statement::CreateTable { .. } = statement {
             let cq = CreateQuery::new(statement).unwrap();
-            let table = Table::new(cq);
+            let table = Table::new(cq).unwrap();
             let expected_column_names = vec![
                 "id".to_string(),
                 "name".to_string(),
@@ -718,45 +2120,1882 @@ mod tests {
         let command = String::from("CREATE TABLE users (id int PRIMARY KEY, name string)");
 
         let dialect = MySqlDialect {};
-        let statement = &Parser::parse_sql(&dialect, command.to_string()).unwrap()[0];
+        let statement = &Parser::parse_sql(&dialect, &command).unwrap()[0];
         if let Statement::CreateTable { .. } = statement {
             let cq = CreateQuery::new(statement).unwrap();
-            let mut table = Table::new(cq);
+            let mut table = Table::new(cq).unwrap();
             let cols = vec!["id".to_string(), "name".to_string()];
-            let val = vec!["1".to_string(), "tahmid".to_string()];
+            let val = vec![Some("1".to_string()), Some("tahmid".to_string())];
             table.does_violate_unique_constraint(&cols, &val).unwrap();
-            table.insert_row(&cols, &vec![val.clone()]);
-            assert_eq!(
-                table.does_violate_unique_constraint(&cols, &val).is_err(),
-                true
-            );
+            table.insert_row(&cols, std::slice::from_ref(&val)).unwrap();
+            assert!(table.does_violate_unique_constraint(&cols, &val).is_err());
         }
     }
-}
 
-mod util {
-    use super::{Cell, PTable, Row};
+    #[test]
+    fn tests_compound_where_clause() {
+        let mut table =
+            create_table("CREATE TABLE users (id int PRIMARY KEY, name string, age int)");
+        table
+            .insert_row(
+                &to_strings(&["id", "name", "age"]),
+                &[
+                    to_values(&["1", "a", "20"]),
+                    to_values(&["2", "b", "30"]),
+                    to_values(&["3", "c", "40"]),
+                    to_values(&["4", "d", "30"]),
+                ],
+            )
+            .unwrap();
+
+        let cases =
+            vec![
+            ("SELECT id FROM users WHERE age = 30 AND id > 2", vec![3]),
+            ("SELECT id FROM users WHERE age = 20 OR age = 40", vec![0, 2]),
+            ("SELECT id FROM users WHERE NOT age = 30", vec![0, 2]),
+            (
+                "SELECT id FROM users WHERE (age = 30 OR name = 'a') AND NOT (id < 2 OR id > 3)",
+                vec![1],
+            ),
+        ];
+        for (query, expected) in cases {
+            let predicate = where_clause(query);
+            assert_eq!(table.evaluate_predicate(&predicate, None), Ok(expected));
+        }
+    }
+    #[test]
+    fn tests_comparison_operators_with_and_without_index() {
+        let mut table = create_table("CREATE TABLE users (id int PRIMARY KEY, age int)");
+        table
+            .insert_row(
+                &to_strings(&["id", "age"]),
+                &[
+                    to_values(&["1", "10"]),
+                    to_values(&["2", "20"]),
+                    to_values(&["3", "30"]),
+                ],
+            )
+            .unwrap();
+
+        // id is served by the primary key index, age is scanned.
+        let cases = vec![
+            ("id <= 2", vec![0, 1]),
+            ("id >= 2", vec![1, 2]),
+            ("id <> 2", vec![0, 2]),
+            ("id != 2", vec![0, 2]),
+            ("age <= 20", vec![0, 1]),
+            ("age >= 20", vec![1, 2]),
+            ("age <> 20", vec![0, 2]),
+        ];
+        for (condition, expected) in cases {
+            let predicate = where_clause(&format!("SELECT id FROM users WHERE {}", condition));
+            assert_eq!(table.evaluate_predicate(&predicate, None), Ok(expected));
+        }
+    }
 
-    pub fn rotate_2d_vec(data: &Vec<Vec<String>>) -> Vec<Vec<&String>> {
-        match data.first() {
-            None => vec![vec![]],
-            _ => {
-                let number_of_rows = data.first().unwrap().len();
-                let number_of_cols = data.len();
+    #[test]
+    fn tests_order_by_multiple_keys() {
+        let mut table =
+            create_table("CREATE TABLE users (id int PRIMARY KEY, name string, age int)");
+        table
+            .insert_row(
+                &to_strings(&["id", "name", "age"]),
+                &[
+                    to_values(&["3", "c", "30"]),
+                    to_values(&["1", "a", "20"]),
+                    to_values(&["4", "d", "20"]),
+                    to_values(&["2", "b", "30"]),
+                ],
+            )
+            .unwrap();
+
+        let cases = vec![
+            (
+                "SELECT id FROM users ORDER BY age, name DESC",
+                vec![2, 1, 0, 3],
+            ),
+            (
+                "SELECT id FROM users ORDER BY age DESC, id",
+                vec![3, 0, 1, 2],
+            ),
+            // Answered by walking the primary key index.
+            ("SELECT id FROM users ORDER BY id", vec![1, 3, 0, 2]),
+            (
+                "SELECT id FROM users WHERE age = 30 ORDER BY id DESC",
+                vec![0, 3],
+            ),
+        ];
+        for (query, expected) in cases {
+            let sq = select_query(query);
+            let matched = match &sq.where_clause {
+                Some(predicate) => table.evaluate_predicate(predicate, None).unwrap(),
+                None => (0..table.row_count()).collect(),
+            };
+            assert_eq!(table.sort_rows(matched, &sq.order_by), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn tests_order_by_null_placement() {
+        let sq = select_query(
+            "SELECT id FROM users ORDER BY a, b DESC, c NULLS LAST, d DESC NULLS FIRST",
+        );
+        let nulls_first = sq
+            .order_by
+            .iter()
+            .map(|key| key.nulls_first)
+            .collect::<Vec<bool>>();
+        assert_eq!(nulls_first, vec![true, false, false, true]);
+    }
+
+    #[test]
+    fn tests_order_by_places_nulls_with_and_without_index() {
+        let mut table = create_table("CREATE TABLE scores (id int, score int)");
+        table
+            .insert_row(
+                &to_strings(&["id", "score"]),
+                &[
+                    to_values(&["1", "20"]),
+                    to_values(&["2", "NULL"]),
+                    to_values(&["3", "10"]),
+                    to_values(&["4", "20"]),
+                    to_values(&["5", "NULL"]),
+                ],
+            )
+            .unwrap();
+        let cases = [
+            ("score", ["2", "5", "3", "1", "4"]),
+            ("score DESC", ["1", "4", "3", "2", "5"]),
+            ("score NULLS LAST", ["3", "1", "4", "2", "5"]),
+            ("score DESC NULLS FIRST", ["2", "5", "1", "4", "3"]),
+            ("score DESC, id DESC", ["4", "1", "3", "5", "2"]),
+        ];
+        for is_indexed in [false, true] {
+            if is_indexed {
+                table
+                    .create_index(&create_index_query(
+                        "CREATE INDEX by_score ON scores (score)",
+                    ))
+                    .unwrap();
+            }
+            for (order_by, expected) in &cases {
+                let query = format!("SELECT id FROM scores ORDER BY {}", order_by);
+                let data = projected_data(&table, &query);
+                assert_eq!(data, Ok(vec![to_strings(expected)]), "{}", query);
+            }
+        }
+    }
+
+    #[test]
+    fn tests_limit_and_offset() {
+        let mut table = create_table("CREATE TABLE users (id int PRIMARY KEY, age int)");
+        let rows = (0..3000)
+            .map(|i| vec![Some(i.to_string()), Some((i % 10).to_string())])
+            .collect::<Vec<Vec<Option<String>>>>();
+        table
+            .insert_row(&to_strings(&["id", "age"]), &rows)
+            .unwrap();
+
+        let cases = vec![
+            ("SELECT id FROM users LIMIT 3", vec![0, 1, 2]),
+            ("SELECT id FROM users LIMIT 2 OFFSET 2999", vec![2999]),
+            ("SELECT id FROM users LIMIT 10, 2", vec![10, 11]),
+            (
+                "SELECT id FROM users WHERE age = 9 LIMIT 2 OFFSET 1",
+                vec![19, 29],
+            ),
+            // Matches spread over several scan batches.
+            (
+                "SELECT id FROM users WHERE age = 9 LIMIT 200",
+                (0..200).map(|i| i * 10 + 9).collect(),
+            ),
+            (
+                "SELECT id FROM users WHERE id > 2995 LIMIT 2",
+                vec![2996, 2997],
+            ),
+            (
+                "SELECT id FROM users ORDER BY id DESC LIMIT 2 OFFSET 1",
+                vec![2998, 2997],
+            ),
+            ("SELECT id FROM users WHERE age = 1 LIMIT 0", vec![]),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(table.get_matching_rows(&select_query(query)), Ok(expected));
+        }
+    }
+    #[test]
+    fn tests_update_rows() {
+        let mut table =
+            create_table("CREATE TABLE users (id int PRIMARY KEY, name string, age int)");
+        table
+            .insert_row(
+                &to_strings(&["id", "name", "age"]),
+                &[
+                    to_values(&["1", "a", "20"]),
+                    to_values(&["2", "b", "30"]),
+                    to_values(&["3", "c", "40"]),
+                ],
+            )
+            .unwrap();
+
+        let updated = table.update_rows(&update_query(
+            "UPDATE users SET age = age * 2 + 1, name = 'x' WHERE age >= 30",
+        ));
+        assert_eq!(updated, Ok(2));
+        assert_eq!(
+            projected_data(&table, "SELECT name, age FROM users").unwrap(),
+            vec![
+                to_strings(&["a", "x", "x"]),
+                to_strings(&["20", "61", "81"])
+            ]
+        );
+
+        // Shifting every key only collides with keys that are being replaced as well.
+        assert_eq!(
+            table.update_rows(&update_query("UPDATE users SET id = id + 1")),
+            Ok(3)
+        );
+        let predicate = where_clause("SELECT id FROM users WHERE id = 4 OR id = 1");
+        assert_eq!(table.evaluate_predicate(&predicate, None), Ok(vec![2]));
+
+        for query in [
+            "UPDATE users SET id = 2 WHERE id = 4",
+            "UPDATE users SET id = 7",
+            "UPDATE users SET age = 'old'",
+        ] {
+            assert!(table.update_rows(&update_query(query)).is_err());
+        }
+        assert_eq!(
+            projected_data(&table, "SELECT id, age FROM users").unwrap(),
+            vec![
+                to_strings(&["2", "3", "4"]),
+                to_strings(&["20", "61", "81"])
+            ]
+        );
+    }
+
+    #[test]
+    fn tests_update_divides_integers_with_truncation() {
+        let mut table = create_table("CREATE TABLE users (id int PRIMARY KEY, age int)");
+        table
+            .insert_row(
+                &to_strings(&["id", "age"]),
+                &[to_values(&["1", "7"]), to_values(&["2", "-7"])],
+            )
+            .unwrap();
+
+        let updated = table.update_rows(&update_query("UPDATE users SET age = age / 2"));
+        assert_eq!(updated, Ok(2));
+        assert_eq!(
+            projected_data(&table, "SELECT age FROM users").unwrap(),
+            vec![to_strings(&["3", "-3"])]
+        );
+        assert!(table
+            .update_rows(&update_query("UPDATE users SET age = age / 0"))
+            .is_err());
+    }
+
+    #[test]
+    fn tests_delete_rows() {
+        let mut table = create_table("CREATE TABLE users (id int PRIMARY KEY, age int)");
+        table
+            .insert_row(
+                &to_strings(&["id", "age"]),
+                &[
+                    to_values(&["4", "10"]),
+                    to_values(&["2", "20"]),
+                    to_values(&["3", "30"]),
+                    to_values(&["1", "40"]),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(
+            table.delete_rows(&delete_query("DELETE FROM users WHERE age = 20 OR id = 4")),
+            Ok(2)
+        );
+        assert_eq!(
+            projected_data(&table, "SELECT id, age FROM users").unwrap(),
+            vec![to_strings(&["3", "1"]), to_strings(&["30", "40"])]
+        );
+        // The primary key index points at the rows' new positions.
+        for (condition, expected) in [
+            ("id = 1", vec![1]),
+            ("id >= 2", vec![0]),
+            ("id = 2", vec![]),
+        ] {
+            let predicate = where_clause(&format!("SELECT id FROM users WHERE {}", condition));
+            assert_eq!(table.evaluate_predicate(&predicate, None), Ok(expected));
+        }
+        table
+            .does_violate_unique_constraint(&to_strings(&["id"]), &to_values(&["4"]))
+            .unwrap();
+
+        assert_eq!(table.delete_rows(&delete_query("DELETE FROM users")), Ok(2));
+        assert_eq!(table.row_count(), 0);
+    }
+
+    #[test]
+    fn tests_aggregate_functions() {
+        let mut table =
+            create_table("CREATE TABLE items (id int PRIMARY KEY, name string, price float)");
+        table
+            .insert_row(
+                &to_strings(&["id", "name", "price"]),
+                &[
+                    to_values(&["3", "b", "1.5"]),
+                    to_values(&["1", "c", "2.5"]),
+                    to_values(&["4", "a", "4"]),
+                    to_values(&["2", "d", "0.5"]),
+                ],
+            )
+            .unwrap();
+
+        let cases = vec![
+            (
+                "SELECT COUNT(*), SUM(price), AVG(price), MIN(price), MAX(name) FROM items",
+                vec!["4", "8.5", "2.125", "0.5", "d"],
+            ),
+            // MIN and MAX of id are read off the primary key index.
+            (
+                "SELECT MIN(id), MAX(id), SUM(id) FROM items",
+                vec!["1", "4", "10"],
+            ),
+            (
+                "SELECT MIN(id), MAX(id), COUNT(name) FROM items WHERE price < 3 AND id > 1",
+                vec!["2", "3", "2"],
+            ),
+            (
+                "SELECT COUNT(*), SUM(id), MAX(id) FROM items WHERE id > 10",
+                vec!["0", "NULL", "NULL"],
+            ),
+        ];
+        for (query, expected) in cases {
+            let data = projected_data(&table, query).unwrap();
+            assert_eq!(
+                data,
+                expected
+                    .iter()
+                    .map(|v| vec![v.to_string()])
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        for query in [
+            "SELECT SUM(name) FROM items",
+            "SELECT name, COUNT(*) FROM items",
+        ] {
+            assert!(projected_data(&table, query).is_err());
+        }
+    }
+
+    #[test]
+    fn tests_group_by_and_having() {
+        let mut table = create_table(
+            "CREATE TABLE customers (id int PRIMARY KEY, country string, city string, credit int)",
+        );
+        table
+            .insert_row(
+                &to_strings(&["id", "country", "city", "credit"]),
+                &[
+                    to_values(&["1", "de", "berlin", "10"]),
+                    to_values(&["2", "us", "boston", "20"]),
+                    to_values(&["3", "de", "bonn", "30"]),
+                    to_values(&["4", "us", "boston", "40"]),
+                    to_values(&["5", "fr", "paris", "50"]),
+                    to_values(&["6", "de", "berlin", "60"]),
+                ],
+            )
+            .unwrap();
+
+        let cases = vec![
+            (
+                "SELECT country, COUNT(*), SUM(credit) FROM customers GROUP BY country",
+                vec![
+                    to_strings(&["de", "us", "fr"]),
+                    to_strings(&["3", "2", "1"]),
+                    to_strings(&["100", "60", "50"]),
+                ],
+            ),
+            (
+                "SELECT country, COUNT(*) FROM customers GROUP BY country HAVING COUNT(*) > 1 ORDER BY country",
+                vec![to_strings(&["de", "us"]), to_strings(&["3", "2"])],
+            ),
+            (
+                "SELECT country, city, MAX(credit) FROM customers WHERE id > 1 GROUP BY country, city \
+                 HAVING NOT country = 'fr' AND MIN(credit) >= 20 ORDER BY city DESC",
+                vec![
+                    to_strings(&["us", "de", "de"]),
+                    to_strings(&["boston", "bonn", "berlin"]),
+                    to_strings(&["40", "30", "60"]),
+                ],
+            ),
+            (
+                "SELECT country FROM customers GROUP BY country ORDER BY country LIMIT 1 OFFSET 1",
+                vec![to_strings(&["fr"])],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(projected_data(&table, query), Ok(expected));
+        }
+
+        for query in [
+            "SELECT city, COUNT(*) FROM customers GROUP BY country",
+            "SELECT id FROM customers WHERE COUNT(*) > 1",
+        ] {
+            assert!(projected_data(&table, query).is_err());
+        }
+    }
+
+    #[test]
+    fn tests_unsupported_selects_are_errors() {
+        for query in [
+            "SELECT t.* FROM t JOIN u ON t.id = u.id",
+            "SELECT id FROM (SELECT id FROM t) AS d",
+            "SELECT id FROM t UNION SELECT id FROM u",
+        ] {
+            let statement = &Parser::parse_sql(&MySqlDialect {}, query).unwrap()[0];
+            assert!(SelectQuery::new(statement).is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn tests_comparing_two_columns_is_an_error() {
+        for query in [
+            "SELECT id FROM t WHERE a = b",
+            "SELECT id FROM t WHERE a > 1 AND a < b",
+        ] {
+            let statement = &Parser::parse_sql(&MySqlDialect {}, query).unwrap()[0];
+            assert!(SelectQuery::new(statement).is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn tests_inner_join() {
+        let mut customers =
+            create_table("CREATE TABLE customers (id int PRIMARY KEY, name string)");
+        customers
+            .insert_row(
+                &to_strings(&["id", "name"]),
+                &[
+                    to_values(&["1", "ann"]),
+                    to_values(&["2", "bob"]),
+                    to_values(&["3", "cid"]),
+                ],
+            )
+            .unwrap();
+        let mut orders =
+            create_table("CREATE TABLE orders (id int PRIMARY KEY, customer_id int, total int)");
+        orders
+            .insert_row(
+                &to_strings(&["id", "customer_id", "total"]),
+                &[
+                    to_values(&["10", "2", "5"]),
+                    to_values(&["11", "1", "7"]),
+                    to_values(&["12", "2", "9"]),
+                    to_values(&["13", "4", "1"]),
+                ],
+            )
+            .unwrap();
+        let joined = |query: &str, tables: Vec<&Table>| {
+            let sq = select_query(query);
+            join::JoinedTables::new(tables, &sq)
+                .and_then(|j| j.select_result(&sq))
+                .map(|result| {
+                    let header = result.columns.iter().map(|(label, _)| label.to_string());
+                    (header.collect::<Vec<String>>(), result.display_data())
+                })
+        };
+
+        // customers.id is probed through its index.
+        let (header, data) = joined(
+            "SELECT o.id, c.name FROM orders o JOIN customers c ON o.customer_id = c.id",
+            vec![&orders, &customers],
+        )
+        .unwrap();
+        assert_eq!(header, to_strings(&["o.id", "c.name"]));
+        assert_eq!(
+            data,
+            vec![
+                to_strings(&["10", "11", "12"]),
+                to_strings(&["bob", "ann", "bob"])
+            ]
+        );
+
+        // Probing the index of the first table and hashing orders.customer_id give the same
+        // pairs.
+        let query =
+            "SELECT name, orders.id FROM customers JOIN orders ON customers.id = customer_id \
+                     WHERE total > 5 ORDER BY orders.id DESC";
+        let expected = vec![to_strings(&["bob", "ann"]), to_strings(&["12", "11"])];
+        assert_eq!(
+            joined(query, vec![&customers, &orders]).unwrap().1,
+            expected
+        );
+        customers.columns[0].is_indexed = false;
+        assert_eq!(
+            joined(query, vec![&customers, &orders]).unwrap().1,
+            expected
+        );
+
+        assert!(joined(
+            "SELECT id FROM orders JOIN customers ON customer_id = customers.id",
+            vec![&orders, &customers],
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn tests_outer_joins() {
+        let (customers, orders, items) = outer_join_tables();
+        let joined = |query: &str| {
+            let sq = select_query(query);
+            let tables = vec![&customers, &orders, &items][..sq.joins.len() + 1].to_vec();
+            join::JoinedTables::new(tables, &sq)
+                .and_then(|j| j.select_result(&sq))
+                .unwrap()
+                .display_data()
+        };
+
+        let cases = vec![
+            (
+                "SELECT name, orders.id FROM customers LEFT JOIN orders ON customers.id = customer_id",
+                vec![to_strings(&["ann", "bob"]), to_strings(&["NULL", "10"])],
+            ),
+            (
+                "SELECT name, orders.id FROM customers RIGHT JOIN orders ON customers.id = customer_id",
+                vec![to_strings(&["bob", "NULL"]), to_strings(&["10", "11"])],
+            ),
+            (
+                "SELECT name, orders.id FROM customers FULL OUTER JOIN orders ON customers.id = customer_id \
+                 ORDER BY name NULLS LAST",
+                vec![to_strings(&["ann", "bob", "NULL"]), to_strings(&["NULL", "10", "11"])],
+            ),
+            // Customers with no orders.
+            (
+                "SELECT name FROM customers LEFT JOIN orders ON customers.id = customer_id WHERE orders.id IS NULL",
+                vec![to_strings(&["ann"])],
+            ),
+            (
+                "SELECT name, orders.id, sku FROM customers LEFT JOIN orders ON customers.id = customer_id \
+                 FULL JOIN items ON orders.id = order_id",
+                vec![
+                    to_strings(&["ann", "bob", "bob", "NULL"]),
+                    to_strings(&["NULL", "10", "10", "NULL"]),
+                    to_strings(&["NULL", "x", "y", "z"]),
+                ],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(joined(query), expected);
+        }
+    }
+
+    #[test]
+    fn tests_not_leaves_padded_rows_unknown() {
+        let (customers, orders, _) = outer_join_tables();
+        let sq = select_query(
+            "SELECT name FROM customers LEFT JOIN orders ON customers.id = customer_id \
+             WHERE NOT orders.id >= 20",
+        );
+        let joined = join::JoinedTables::new(vec![&customers, &orders], &sq).unwrap();
+        // ann has no order, so orders.id is NULL there and NOT (NULL >= 20) is unknown.
+        assert_eq!(
+            joined.select_result(&sq).unwrap().display_data(),
+            vec![to_strings(&["bob"])]
+        );
+    }
+
+    /// The customers, orders and items the outer join tests run on.
+    fn outer_join_tables() -> (Table, Table, Table) {
+        let mut customers =
+            create_table("CREATE TABLE customers (id int PRIMARY KEY, name string)");
+        customers
+            .insert_row(
+                &to_strings(&["id", "name"]),
+                &[to_values(&["1", "ann"]), to_values(&["2", "bob"])],
+            )
+            .unwrap();
+        let mut orders = create_table("CREATE TABLE orders (id int PRIMARY KEY, customer_id int)");
+        orders
+            .insert_row(
+                &to_strings(&["id", "customer_id"]),
+                &[to_values(&["10", "2"]), to_values(&["11", "3"])],
+            )
+            .unwrap();
+        let mut items = create_table("CREATE TABLE items (order_id int, sku string)");
+        items
+            .insert_row(
+                &to_strings(&["order_id", "sku"]),
+                &[
+                    to_values(&["10", "x"]),
+                    to_values(&["10", "y"]),
+                    to_values(&["12", "z"]),
+                ],
+            )
+            .unwrap();
+        (customers, orders, items)
+    }
+
+    #[test]
+    fn tests_null_values() {
+        let mut table =
+            create_table("CREATE TABLE users (id int PRIMARY KEY, name string NOT NULL, age int)");
+        table
+            .insert_row(
+                &to_strings(&["id", "name", "age"]),
+                &[
+                    to_values(&["1", "a", "20"]),
+                    to_values(&["2", "b", "NULL"]),
+                    to_values(&["3", "c", "40"]),
+                ],
+            )
+            .unwrap();
+
+        // NOT NULL columns and primary keys reject NULL, leaving the table untouched.
+        for row in [["4", "NULL", "1"], ["NULL", "d", "1"]] {
+            assert!(table
+                .insert_row(&to_strings(&["id", "name", "age"]), &[to_values(&row)])
+                .is_err());
+        }
+        assert_eq!(table.rows.get("id").unwrap().count(), 3);
+
+        let cases = vec![
+            (
+                "SELECT name FROM users WHERE age IS NULL",
+                vec![to_strings(&["b"])],
+            ),
+            (
+                "SELECT name FROM users WHERE age IS NOT NULL",
+                vec![to_strings(&["a", "c"])],
+            ),
+            // Comparing with NULL is unknown, and so is its negation.
+            (
+                "SELECT name FROM users WHERE NOT age = 20",
+                vec![to_strings(&["c"])],
+            ),
+            (
+                "SELECT name, age FROM users ORDER BY age",
+                vec![
+                    to_strings(&["b", "a", "c"]),
+                    to_strings(&["NULL", "20", "40"]),
+                ],
+            ),
+            (
+                "SELECT COUNT(*), COUNT(age), AVG(age) FROM users",
+                vec![to_strings(&["3"]), to_strings(&["2"]), to_strings(&["30"])],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(projected_data(&table, query).unwrap(), expected);
+        }
+
+        assert_eq!(
+            table.update_rows(&update_query("UPDATE users SET age = NULL WHERE id = 1")),
+            Ok(1)
+        );
+        assert!(table
+            .update_rows(&update_query("UPDATE users SET name = NULL"))
+            .is_err());
+        let predicate = where_clause("SELECT id FROM users WHERE age IS NULL");
+        assert_eq!(table.evaluate_predicate(&predicate, None), Ok(vec![0, 1]));
+    }
+
+    fn products_with_defaults() -> Table {
+        create_table(
+            "CREATE TABLE products (id int PRIMARY KEY, name string NOT NULL, \
+             price float NOT NULL DEFAULT '0.0000', stock int DEFAULT NULL, note string)",
+        )
+    }
+
+    #[test]
+    fn tests_omitted_columns_take_their_default() {
+        let mut table = products_with_defaults();
+        table
+            .insert_row(
+                &to_strings(&["name", "id"]),
+                &[to_values(&["tea", "1"]), to_values(&["milk", "2"])],
+            )
+            .unwrap();
+        table
+            .insert_row(
+                &to_strings(&["id", "name", "price", "stock"]),
+                &[to_values(&["3", "rice", "2.5", "7"])],
+            )
+            .unwrap();
+        assert_eq!(
+            projected_data(&table, "SELECT id, price, stock, note FROM products").unwrap(),
+            vec![
+                to_strings(&["1", "2", "3"]),
+                to_strings(&["0", "0", "2.5"]),
+                to_strings(&["NULL", "NULL", "7"]),
+                to_strings(&["NULL", "NULL", "NULL"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn tests_omitted_column_without_default_is_refused() {
+        let mut table = products_with_defaults();
+        // `name` is NOT NULL without a default, and the primary key can't be left out either.
+        for cols in [["id", "price"], ["name", "price"]] {
+            assert!(table
+                .insert_row(&to_strings(&cols), &[to_values(&["4", "1.0"])])
+                .is_err());
+        }
+        assert_eq!(table.rows.get("note").unwrap().count(), 0);
+    }
+
+    #[test]
+    fn tests_insert_rejects_duplicates_within_batch() {
+        let mut table = create_table(
+            "CREATE TABLE users (id int PRIMARY KEY, first string, last string, \
+             UNIQUE (first, last))",
+        );
+        let cols = to_strings(&["id", "first", "last"]);
+        table
+            .insert_row(&cols, &[to_values(&["1", "ann", "lee"])])
+            .unwrap();
+
+        for batch in [
+            vec![
+                to_values(&["2", "bob", "ray"]),
+                to_values(&["2", "cy", "ray"]),
+            ],
+            vec![
+                to_values(&["2", "bob", "ray"]),
+                to_values(&["3", "bob", "ray"]),
+            ],
+        ] {
+            assert!(table.insert_row(&cols, &batch).is_err());
+        }
+        assert_eq!(table.row_count(), 1);
+
+        // NULLs never collide.
+        table
+            .insert_row(
+                &cols,
+                &[
+                    to_values(&["2", "bob", "NULL"]),
+                    to_values(&["3", "bob", "NULL"]),
+                ],
+            )
+            .unwrap();
+        assert_eq!(table.row_count(), 3);
+    }
+
+    fn counters() -> Table {
+        let mut table = create_table(
+            "CREATE TABLE counters (id BIGINT PRIMARY KEY, small SMALLINT, tiny TINYINT, \
+             flag TINYINT(1))",
+        );
+        table
+            .insert_row(
+                &to_strings(&["id", "small", "tiny", "flag"]),
+                &[
+                    to_values(&["9000000000", "-32768", "127", "1"]),
+                    to_values(&["-9000000000", "300", "-5", "false"]),
+                    to_values(&["1", "7", "0", "0"]),
+                ],
+            )
+            .unwrap();
+        table
+    }
+
+    #[test]
+    fn tests_integers_out_of_their_width_are_refused() {
+        let mut table = counters();
+        for (col, value) in [
+            ("tiny", "128"),
+            ("small", "40000"),
+            ("flag", "2"),
+            ("small", "1.5"),
+        ] {
+            assert!(table
+                .insert_row(&to_strings(&["id", col]), &[to_values(&["2", value])])
+                .is_err());
+        }
+        assert_eq!(table.row_count(), 3);
+    }
+
+    #[test]
+    fn tests_tinyint_1_is_a_bool() {
+        assert_eq!(
+            counters().get_column("flag".to_string()).datatype,
+            DataType::Bool
+        );
+    }
+
+    #[test]
+    fn tests_integer_comparisons_across_widths() {
+        let table = counters();
+        let cases = vec![
+            (
+                "SELECT id FROM counters WHERE id > 2147483647",
+                vec![to_strings(&["9000000000"])],
+            ),
+            // Terms outside the range of the column still compare.
+            (
+                "SELECT id FROM counters WHERE tiny < 1000",
+                vec![to_strings(&["9000000000", "-9000000000", "1"])],
+            ),
+            (
+                "SELECT id FROM counters WHERE small >= -40000 AND small < 100",
+                vec![to_strings(&["9000000000", "1"])],
+            ),
+            (
+                "SELECT id FROM counters WHERE flag = 1",
+                vec![to_strings(&["9000000000"])],
+            ),
+            (
+                "SELECT SUM(small), MAX(id) FROM counters",
+                vec![to_strings(&["-32461"]), to_strings(&["9000000000"])],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(projected_data(&table, query).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn tests_integer_sum_is_exact() {
+        let mut table = create_table("CREATE TABLE counters (a tinyint, b bigint)");
+        table
+            .insert_row(
+                &to_strings(&["a", "b"]),
+                &[
+                    to_values(&["100", "9007199254740993"]),
+                    to_values(&["100", "1"]),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            projected_data(&table, "SELECT SUM(a), SUM(b), AVG(a) FROM counters"),
+            Ok(vec![
+                to_strings(&["200"]),
+                to_strings(&["9007199254740994"]),
+                to_strings(&["100"]),
+            ])
+        );
+    }
+
+    #[test]
+    fn tests_integer_sum_past_bigint_is_out_of_range() {
+        let mut table = create_table("CREATE TABLE counters (b bigint)");
+        table
+            .insert_row(
+                &to_strings(&["b"]),
+                &[to_values(&["9223372036854775807"]), to_values(&["1"])],
+            )
+            .unwrap();
+        assert!(projected_data(&table, "SELECT SUM(b) FROM counters").is_err());
+    }
+
+    fn users_by_age() -> Table {
+        let mut table =
+            create_table("CREATE TABLE users (id int PRIMARY KEY, name string, age int)");
+        table
+            .insert_row(
+                &to_strings(&["id", "name", "age"]),
+                &[
+                    to_values(&["1", "a", "30"]),
+                    to_values(&["2", "b", "20"]),
+                    to_values(&["3", "c", "30"]),
+                    to_values(&["4", "d", "NULL"]),
+                ],
+            )
+            .unwrap();
+        table
+    }
+
+    /// `users_by_age` with a non-unique index on the ages and a unique one on the names.
+    fn indexed_users() -> Table {
+        let mut table = users_by_age();
+        table
+            .create_index(&create_index_query("CREATE INDEX by_age ON users (age)"))
+            .unwrap();
+        table
+            .create_index(&create_index_query("CREATE UNIQUE INDEX ON users (name)"))
+            .unwrap();
+        table
+    }
+
+    #[test]
+    fn tests_unique_index_needs_distinct_values() {
+        let mut table = users_by_age();
+        // Ages repeat, so a unique index can't be built on them.
+        assert!(table
+            .create_index(&create_index_query(
+                "CREATE UNIQUE INDEX by_age ON users (age)"
+            ))
+            .is_err());
+        assert!(!table.get_column("age".to_string()).is_indexed);
+    }
+
+    #[test]
+    fn tests_non_unique_index_holds_duplicates() {
+        let mut table = indexed_users();
+        assert!(table.has_index("by_age") && table.has_index("users_name_idx"));
+        table
+            .insert_row(
+                &to_strings(&["id", "name", "age"]),
+                &[to_values(&["5", "e", "20"])],
+            )
+            .unwrap();
+        let age = table.get_column("age".to_string());
+        assert_eq!(age.index.get_idx_data("30"), Ok(&[0, 2][..]));
+        assert_eq!(age.index.get_idx_data("20"), Ok(&[1, 4][..]));
+    }
+
+    #[test]
+    fn tests_unique_index_rejects_duplicates() {
+        let mut table = indexed_users();
+        assert!(table
+            .insert_row(
+                &to_strings(&["id", "name", "age"]),
+                &[to_values(&["6", "a", "40"])]
+            )
+            .is_err());
+        assert_eq!(table.row_count(), 4);
+    }
+
+    #[test]
+    fn tests_index_lookups_after_update_and_delete() {
+        let mut table = indexed_users();
+        table
+            .insert_row(
+                &to_strings(&["id", "name", "age"]),
+                &[to_values(&["5", "e", "20"])],
+            )
+            .unwrap();
+        table
+            .update_rows(&update_query("UPDATE users SET age = 40 WHERE id = 1"))
+            .unwrap();
+        table
+            .delete_rows(&delete_query("DELETE FROM users WHERE id = 2"))
+            .unwrap();
+
+        let age = table.get_column("age".to_string());
+        assert_eq!(age.index.get_row_groups_in_order().concat(), vec![3, 1, 0]);
+        assert_eq!(age.index.get_idx_data("30"), Ok(&[1][..]));
+        let cases = vec![
+            (
+                "SELECT id FROM users WHERE age >= 20 AND age < 40",
+                vec![to_strings(&["3", "5"])],
+            ),
+            (
+                "SELECT id FROM users WHERE NOT age = 30",
+                vec![to_strings(&["1", "5"])],
+            ),
+            (
+                "SELECT id FROM users WHERE name = 'c'",
+                vec![to_strings(&["3"])],
+            ),
+            (
+                "SELECT id FROM users ORDER BY age DESC",
+                vec![to_strings(&["1", "3", "5", "4"])],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(projected_data(&table, query).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn tests_drop_index() {
+        let mut table = indexed_users();
+        table.drop_index("by_age").unwrap();
+        assert!(!table.has_index("by_age") && table.drop_index("by_age").is_err());
+        assert!(!table.get_column("age".to_string()).is_indexed);
+        assert_eq!(
+            projected_data(&table, "SELECT id FROM users WHERE age = 20"),
+            Ok(vec![to_strings(&["2"])])
+        );
+    }
+
+    #[test]
+    fn tests_float_rejects_nan_and_infinity() {
+        let mut table = create_table("CREATE TABLE items (id int, price float)");
+        table
+            .insert_row(&to_strings(&["id", "price"]), &[to_values(&["1", "150"])])
+            .unwrap();
+        for price in ["NaN", "inf", "-infinity", "1e40"] {
+            assert!(table
+                .insert_row(&to_strings(&["id", "price"]), &[to_values(&["2", price])])
+                .is_err());
+        }
+        assert!(table
+            .update_rows(&update_query("UPDATE items SET price = 'NaN'"))
+            .is_err());
+    }
+
+    #[test]
+    fn tests_float_nan_term_is_an_error_scanned_and_indexed() {
+        let mut table = create_table("CREATE TABLE items (id int, price float)");
+        table
+            .insert_row(&to_strings(&["id", "price"]), &[to_values(&["1", "150"])])
+            .unwrap();
+        // Scanned and indexed, a NaN term is an error rather than an answer.
+        let query = "SELECT id FROM items WHERE price < 'NaN'";
+        assert!(projected_data(&table, query).is_err());
+        table
+            .create_index(&create_index_query(
+                "CREATE INDEX by_price ON items (price)",
+            ))
+            .unwrap();
+        assert!(projected_data(&table, query).is_err());
+    }
+
+    fn scores() -> Table {
+        let mut table = create_table(
+            "CREATE TABLE scores (player int, game int, round int, score int, PRIMARY KEY (player, game))",
+        );
+        table
+            .insert_row(
+                &to_strings(&["player", "game", "round", "score"]),
+                &[
+                    to_values(&["1", "1", "1", "10"]),
+                    to_values(&["1", "2", "1", "20"]),
+                    to_values(&["2", "1", "2", "30"]),
+                    to_values(&["1", "3", "2", "40"]),
+                    to_values(&["2", "2", "2", "50"]),
+                ],
+            )
+            .unwrap();
+        table
+    }
+
+    /// `scores` with an index on (round, score) next to its primary key.
+    fn indexed_scores() -> Table {
+        let mut table = scores();
+        table
+            .create_index(&create_index_query(
+                "CREATE INDEX by_round ON scores (round, score)",
+            ))
+            .unwrap();
+        table
+    }
+
+    /// How many of the conjuncts of `query` a multi-column index answers, `None` when none
+    /// of them is used.
+    fn composite_columns_used(table: &Table, query: &str) -> Option<usize> {
+        let predicate = where_clause(query);
+        let conjuncts = composite::conjuncts(&predicate);
+        table
+            .composite_index_lookup(&conjuncts)
+            .unwrap()
+            .map(|(_, used)| used.len())
+    }
+
+    #[test]
+    fn tests_composite_primary_key_is_not_null() {
+        let table = scores();
+        assert!(["player", "game"]
+            .iter()
+            .all(|c| !table.get_column(c.to_string()).is_nullable));
+    }
+
+    #[test]
+    fn tests_composite_key_rejects_duplicate_tuples() {
+        let mut table = scores();
+        // Only the pair has to be unique.
+        assert!(table
+            .insert_row(
+                &to_strings(&["player", "game", "round", "score"]),
+                &[to_values(&["2", "1", "3", "60"])]
+            )
+            .is_err());
+        assert!(table
+            .update_rows(&update_query("UPDATE scores SET game = 1 WHERE score = 40"))
+            .is_err());
+        assert!(table
+            .update_rows(&update_query("UPDATE scores SET game = 5 WHERE player = 1"))
+            .is_err());
+        assert!(table
+            .create_index(&create_index_query(
+                "CREATE UNIQUE INDEX by_round ON scores (round, player)"
+            ))
+            .is_err());
+        assert_eq!(table.row_count(), 5);
+    }
+
+    #[test]
+    fn tests_composite_primary_key_cannot_be_dropped() {
+        let mut table = indexed_scores();
+        assert!(table.has_index("by_round") && !table.has_index("PRIMARY"));
+        table.drop_index("by_round").unwrap();
+        assert!(table.drop_index("PRIMARY").is_err());
+        assert!(table
+            .insert_row(
+                &to_strings(&["player", "game", "round", "score"]),
+                &[to_values(&["2", "2", "3", "60"])]
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn tests_composite_lookup_by_prefix_and_range() {
+        let table = indexed_scores();
+        // An equal first column and a range on the second both go to the index.
+        assert_eq!(
+            composite_columns_used(&table, "SELECT * FROM scores WHERE player = 1 AND game > 1"),
+            Some(2)
+        );
+        assert_eq!(
+            composite_columns_used(&table, "SELECT * FROM scores WHERE score > 1 AND round = 2"),
+            Some(2)
+        );
+        assert_eq!(
+            composite_columns_used(&table, "SELECT * FROM scores WHERE player = 1"),
+            Some(1)
+        );
+        // Without the first column no index helps.
+        assert_eq!(
+            composite_columns_used(&table, "SELECT * FROM scores WHERE game = 1"),
+            None
+        );
+    }
+
+    #[test]
+    fn tests_composite_index_after_update_and_delete() {
+        let mut table = indexed_scores();
+        table
+            .update_rows(&update_query(
+                "UPDATE scores SET game = game + 10 WHERE player = 2",
+            ))
+            .unwrap();
+        table
+            .delete_rows(&delete_query("DELETE FROM scores WHERE score = 20"))
+            .unwrap();
+
+        let cases = vec![
+            (
+                "SELECT score FROM scores WHERE player = 1 AND game > 1",
+                vec![to_strings(&["40"])],
+            ),
+            (
+                "SELECT score FROM scores WHERE player = 2 AND game = 11",
+                vec![to_strings(&["30"])],
+            ),
+            (
+                "SELECT score FROM scores WHERE round = 2 AND score <= 40 AND player = 1",
+                vec![to_strings(&["40"])],
+            ),
+            (
+                "SELECT score FROM scores WHERE round = 2 OR player = 1",
+                vec![to_strings(&["10", "30", "40", "50"])],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(projected_data(&table, query).unwrap(), expected);
+        }
+    }
+
+    fn constrained_users() -> Table {
+        let mut table = create_table(
+            "CREATE TABLE users (id int PRIMARY KEY UNIQUE, email string UNIQUE NOT NULL, \
+             age int CHECK (age >= 0), name string, \
+             CONSTRAINT adult CHECK (age >= 18 OR name IS NULL), UNIQUE (name, age))",
+        );
+        table
+            .insert_row(
+                &to_strings(&["id", "email", "age", "name"]),
+                &[
+                    to_values(&["1", "a", "20", "x"]),
+                    to_values(&["2", "b", "5", "NULL"]),
+                    to_values(&["3", "c", "NULL", "y"]),
+                ],
+            )
+            .unwrap();
+        table
+    }
+
+    /// The error inserting `row` into `constrained_users` fails with.
+    fn constraint_error(table: &mut Table, row: &[&str]) -> String {
+        let cols = to_strings(&["id", "email", "age", "name"]);
+        table.insert_row(&cols, &[to_values(row)]).unwrap_err()
+    }
+
+    #[test]
+    fn tests_constraints_from_create_table() {
+        let table = constrained_users();
+        let email = table.get_column("email".to_string());
+        assert!(email.is_unique && !email.is_nullable);
+        assert!(table.get_column("id".to_string()).is_primary_key);
+        // Unnamed checks are numbered after the table.
+        assert_eq!(
+            table
+                .checks
+                .iter()
+                .map(|c| c.name.to_string())
+                .collect::<Vec<String>>(),
+            to_strings(&["users_chk_1", "adult"])
+        );
+
+        let statement = &Parser::parse_sql(
+            &MySqlDialect {},
+            "CREATE TABLE t (a int, CONSTRAINT c CHECK (b > 0))",
+        )
+        .unwrap()[0];
+        assert!(Table::new(CreateQuery::new(statement).unwrap()).is_err());
+    }
+
+    #[test]
+    fn tests_unique_constraints_name_their_index() {
+        let mut table = constrained_users();
+        for (row, error) in [
+            (
+                ["4", "a", "30", "z"],
+                "unique constraint violation for index users_email_idx",
+            ),
+            (
+                ["4", "d", "20", "x"],
+                "unique constraint violation for index users_name_age_idx",
+            ),
+        ] {
+            let err = constraint_error(&mut table, &row);
+            assert!(err.contains(error), "{}", err);
+        }
+        assert_eq!(table.row_count(), 3);
+    }
+
+    #[test]
+    fn tests_check_constraints_pass_on_unknown() {
+        // Row 2 has no name and row 3 no age, which leaves a check unknown rather than false.
+        let mut table = constrained_users();
+        for (row, error) in [
+            (
+                ["4", "d", "-1", "NULL"],
+                "check constraint users_chk_1 is violated",
+            ),
+            (["4", "d", "17", "z"], "check constraint adult is violated"),
+        ] {
+            let err = constraint_error(&mut table, &row);
+            assert!(err.contains(error), "{}", err);
+        }
+        assert_eq!(table.row_count(), 3);
+    }
+
+    #[test]
+    fn tests_not_null_refuses_null() {
+        let mut table = constrained_users();
+        let err = constraint_error(&mut table, &["4", "NULL", "30", "z"]);
+        assert!(err.contains("cannot be NULL"), "{}", err);
+    }
+
+    #[test]
+    fn tests_updates_are_checked_against_constraints() {
+        let mut table = constrained_users();
+        let err = table
+            .update_rows(&update_query("UPDATE users SET age = 10 WHERE id = 3"))
+            .unwrap_err();
+        assert!(err.contains("check constraint adult"), "{}", err);
+        table
+            .update_rows(&update_query(
+                "UPDATE users SET name = NULL, age = 10 WHERE id = 3",
+            ))
+            .unwrap();
+        assert_eq!(table.get_value("age", 2), Some("10".to_string()));
+    }
+
+    fn float_prices() -> Table {
+        let mut table = create_table("CREATE TABLE prices (price float PRIMARY KEY, name string)");
+        table
+            .insert_row(
+                &to_strings(&["price", "name"]),
+                &[
+                    to_values(&["2.5", "tea"]),
+                    to_values(&["-0.0", "free"]),
+                    to_values(&["-1.25", "refund"]),
+                    to_values(&["19.99", "rice"]),
+                ],
+            )
+            .unwrap();
+        table
+    }
+
+    #[test]
+    fn tests_float_index_finds_equal_values_spelled_differently() {
+        let table = float_prices();
+        assert!(table
+            .does_violate_unique_constraint(&to_strings(&["price"]), &to_values(&["2.50"]))
+            .is_err());
+        assert!(table
+            .does_violate_unique_constraint(&to_strings(&["price"]), &to_values(&["0"]))
+            .is_err());
+    }
+
+    #[test]
+    fn tests_float_index_orders_keys_numerically() {
+        let table = float_prices();
+        let price = table.get_column("price".to_string());
+        assert_eq!(
+            price.index.get_row_groups_in_order().concat(),
+            vec![2, 1, 0, 3]
+        );
+    }
+
+    #[test]
+    fn tests_float_index_answers_comparisons() {
+        let table = float_prices();
+        let cases = vec![
+            (
+                "SELECT name FROM prices WHERE price >= 0",
+                vec![to_strings(&["tea", "free", "rice"])],
+            ),
+            (
+                "SELECT name FROM prices WHERE price < 2.5",
+                vec![to_strings(&["free", "refund"])],
+            ),
+            (
+                "SELECT name FROM prices WHERE price = 0",
+                vec![to_strings(&["free"])],
+            ),
+            (
+                "SELECT name FROM prices WHERE price > 100",
+                vec![to_strings(&[])],
+            ),
+            (
+                "SELECT MIN(price), MAX(price) FROM prices",
+                vec![to_strings(&["-1.25"]), to_strings(&["19.99"])],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(projected_data(&table, query).unwrap(), expected);
+        }
+    }
+
+    fn prices() -> Table {
+        let mut table = create_table(
+            "CREATE TABLE prices (id int, amount DECIMAL(19,4), rate NUMERIC(5,2) PRIMARY KEY)",
+        );
+        table
+            .insert_row(
+                &to_strings(&["id", "amount", "rate"]),
+                &[
+                    to_values(&["1", "0.1", "12.345"]),
+                    to_values(&["2", "0.2", "-0.005"]),
+                    to_values(&["3", "NULL", "999.99"]),
+                    to_values(&["4", "123456789012345.0001", "7"]),
+                ],
+            )
+            .unwrap();
+        table
+    }
+
+    #[test]
+    fn tests_decimal_values_are_rounded_to_their_scale() {
+        assert_eq!(
+            projected_data(&prices(), "SELECT amount, rate FROM prices WHERE id < 4").unwrap(),
+            vec![
+                to_strings(&["0.1000", "0.2000", "NULL"]),
+                to_strings(&["12.35", "-0.01", "999.99"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn tests_decimal_refuses_values_past_its_precision() {
+        let mut table = prices();
+        // Three integer digits fit NUMERIC(5,2), rounding may not push a value past them.
+        for rate in ["1000", "999.995", "1e3", "abc"] {
+            assert!(table
+                .insert_row(&to_strings(&["id", "rate"]), &[to_values(&["5", rate])])
+                .is_err());
+        }
+        assert_eq!(table.row_count(), 4);
+    }
+
+    #[test]
+    fn tests_decimal_aggregates_are_exact() {
+        assert_eq!(
+            projected_data(
+                &prices(),
+                "SELECT SUM(amount), AVG(amount), MAX(rate), SUM(rate) FROM prices"
+            )
+            .unwrap(),
+            vec![
+                to_strings(&["123456789012345.3001"]),
+                to_strings(&["41152263004115.10003333"]),
+                to_strings(&["999.99"]),
+                to_strings(&["1019.33"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn tests_decimal_comparisons_and_ordering() {
+        let table = prices();
+        let cases = vec![
+            (
+                "SELECT id FROM prices WHERE amount > '0.15'",
+                vec![to_strings(&["2", "4"])],
+            ),
+            // `rate` is the primary key, so this one is answered from its index.
+            (
+                "SELECT id FROM prices WHERE rate <= 7",
+                vec![to_strings(&["2", "4"])],
+            ),
+            (
+                "SELECT id FROM prices ORDER BY rate DESC",
+                vec![to_strings(&["3", "1", "4", "2"])],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(projected_data(&table, query).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn tests_decimal_terms_compare_at_full_precision() {
+        let mut table = create_table(
+            "CREATE TABLE prices (id int, amount decimal(5,2), rate decimal(5,2) PRIMARY KEY, \
+             UNIQUE (id, amount))",
+        );
+        table
+            .insert_row(
+                &to_strings(&["id", "amount", "rate"]),
+                &[
+                    to_values(&["1", "0.15", "0.15"]),
+                    to_values(&["2", "12.35", "12.35"]),
+                    to_values(&["3", "-0.01", "-0.01"]),
+                ],
+            )
+            .unwrap();
+
+        // `rate` is answered from its index, `amount` by a scan and (id, amount) from the
+        // multi-column index.
+        let cases = vec![
+            ("rate >= 0.151 OR amount >= 0.151", vec!["2"]),
+            ("rate = 12.345 OR amount = 12.345", vec![]),
+            ("rate <> 12.345 AND amount <> 12.345", vec!["1", "2", "3"]),
+            ("rate < 100000 AND amount < 100000", vec!["1", "2", "3"]),
+            ("rate <= -0.001 AND amount <= -0.001", vec!["3"]),
+            (
+                "rate > -100000000000000000000000000000000000000000 \
+                 AND amount < 100000000000000000000000000000000000000000",
+                vec!["1", "2", "3"],
+            ),
+            ("id = 2 AND amount > 12.349", vec!["2"]),
+            ("id = 2 AND amount = 12.349", vec![]),
+        ];
+        for (condition, expected) in cases {
+            let query = format!("SELECT id FROM prices WHERE {}", condition);
+            let data = projected_data(&table, &query);
+            assert_eq!(data, Ok(vec![to_strings(&expected)]), "{}", query);
+        }
+    }
+
+    #[test]
+    fn tests_decimal_check_terms_compare_at_full_precision() {
+        let mut limits =
+            create_table("CREATE TABLE limits (amount decimal(5,2) CHECK (amount >= 0.004))");
+        let cols = to_strings(&["amount"]);
+        assert!(limits.insert_row(&cols, &[to_values(&["0.00"])]).is_err());
+        assert!(limits.insert_row(&cols, &[to_values(&["0.01"])]).is_ok());
+    }
+
+    #[test]
+    fn tests_decimal_update_arithmetic_is_exact() {
+        let mut table = create_table("CREATE TABLE accounts (id int, balance decimal(20,4))");
+        table
+            .insert_row(
+                &to_strings(&["id", "balance"]),
+                &[to_values(&["1", "123456789012345.0001"])],
+            )
+            .unwrap();
+
+        for (query, expected) in [
+            (
+                "UPDATE accounts SET balance = balance + 1",
+                "123456789012346.0001",
+            ),
+            (
+                "UPDATE accounts SET balance = balance * 2",
+                "246913578024692.0002",
+            ),
+            (
+                "UPDATE accounts SET balance = balance - 0.0002",
+                "246913578024692.0000",
+            ),
+            (
+                "UPDATE accounts SET balance = balance / 3",
+                "82304526008230.6667",
+            ),
+            ("UPDATE accounts SET balance = balance % 1", "0.6667"),
+        ] {
+            assert_eq!(table.update_rows(&update_query(query)), Ok(1));
+            assert_eq!(table.get_value("balance", 0), Some(expected.to_string()));
+        }
+        assert!(table
+            .update_rows(&update_query("UPDATE accounts SET balance = balance / 0.0"))
+            .is_err());
+    }
+
+    #[test]
+    fn tests_decimal_rounding_past_the_largest_value_is_out_of_range() {
+        let decimal = Decimal {
+            precision: Decimal::MAX_PRECISION,
+            scale: 0,
+        };
+        assert!(decimal.parse(&format!("{}.5", i128::MAX)).is_err());
+        assert_eq!(decimal.parse("12.5"), Ok(13));
+    }
+
+    #[test]
+    fn tests_temporal_types() {
+        let mut table = create_table(
+            "CREATE TABLE orders (id int, order_date DATETIME, shipped DATE, due TIME, \
+             paid TIMESTAMP PRIMARY KEY)",
+        );
+        table
+            .insert_row(
+                &to_strings(&["id", "order_date", "shipped", "due", "paid"]),
+                &[
+                    to_values(&[
+                        "1",
+                        "2006-01-15 10:30:00",
+                        "2006-01-20",
+                        "09:00",
+                        "2006-01-15T11:00:00",
+                    ]),
+                    to_values(&[
+                        "2",
+                        "2005-12-31 23:59:59",
+                        "NULL",
+                        "17:45:30",
+                        "2005-12-31 23:59:59",
+                    ]),
+                    to_values(&[
+                        "3",
+                        "2006-01-01",
+                        "1969-07-20",
+                        "00:00:00",
+                        "2006-03-01 08:00:00",
+                    ]),
+                ],
+            )
+            .unwrap();
+        for value in ["2006-02-30", "yesterday"] {
+            assert!(table
+                .insert_row(
+                    &to_strings(&["paid", "shipped"]),
+                    &[to_values(&["2007-01-01", value])]
+                )
+                .is_err());
+        }
+
+        let cases = vec![
+            (
+                "SELECT id FROM orders WHERE order_date > '2006-01-01'",
+                vec![to_strings(&["1"])],
+            ),
+            (
+                "SELECT id FROM orders WHERE order_date >= '2006-01-01'",
+                vec![to_strings(&["1", "3"])],
+            ),
+            (
+                "SELECT id FROM orders WHERE shipped < '2000-01-01'",
+                vec![to_strings(&["3"])],
+            ),
+            (
+                "SELECT id FROM orders WHERE due >= '09:00' AND due < '18:00'",
+                vec![to_strings(&["1", "2"])],
+            ),
+            // `paid` is the primary key, so this one is answered from its index.
+            (
+                "SELECT paid FROM orders WHERE paid < '2006-02-01'",
+                vec![to_strings(&["2006-01-15 11:00:00", "2005-12-31 23:59:59"])],
+            ),
+            (
+                "SELECT order_date, shipped, due FROM orders ORDER BY order_date",
+                vec![
+                    to_strings(&[
+                        "2005-12-31 23:59:59",
+                        "2006-01-01 00:00:00",
+                        "2006-01-15 10:30:00",
+                    ]),
+                    to_strings(&["NULL", "1969-07-20", "2006-01-20"]),
+                    to_strings(&["17:45:30", "00:00:00", "09:00:00"]),
+                ],
+            ),
+            (
+                "SELECT MIN(shipped), MAX(order_date) FROM orders",
+                vec![
+                    to_strings(&["1969-07-20"]),
+                    to_strings(&["2006-01-15 10:30:00"]),
+                ],
+            ),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(projected_data(&table, query).unwrap(), expected);
+        }
+    }
+}
+
+mod util {
+    use super::{decimal, Arithmetic, Binary, Cell, PTable, Row, Sum};
+    use std::borrow::Borrow;
+    use std::cmp::Ordering;
+    use std::collections::BTreeMap;
+    use std::ops::Bound::{Excluded, Included, Unbounded};
+
+    /// Positions out of `rows` whose value in `data` satisfies `value op term`.
+    pub fn scan<T: PartialOrd>(data: &[T], rows: &[usize], term: &T, op: &Binary) -> Vec<usize> {
+        rows.iter()
+            .copied()
+            .filter(|idx| satisfies(&data[*idx], op, term))
+            .collect()
+    }
+
+    /// Sum of the values of an integer column at `rows`. It cannot overflow an i128, there are
+    /// fewer than 2^63 rows of values below 2^63.
+    pub fn exact_sum<T: Copy + Into<i64>>(data: &[T], rows: &[usize]) -> Sum {
+        Sum::Integer(rows.iter().map(|idx| i128::from(data[*idx].into())).sum())
+    }
+
+    /// Like `scan` for an integer column, comparing its values as i64 so that a term outside
+    /// the range of the column still compares.
+    pub fn scan_widened<T: Copy + Into<i64>>(
+        data: &[T],
+        rows: &[usize],
+        term: &str,
+        op: &Binary,
+    ) -> Result<Vec<usize>, String> {
+        let term = term.parse::<i64>().map_err(|e| e.to_string())?;
+        Ok(rows
+            .iter()
+            .copied()
+            .filter(|idx| satisfies(&data[*idx].into(), op, &term))
+            .collect())
+    }
+
+    pub fn satisfies<T: PartialOrd>(value: &T, op: &Binary, term: &T) -> bool {
+        match op {
+            Binary::Eq => value == term,
+            Binary::NotEq => value != term,
+            Binary::Gt => value > term,
+            Binary::GtEq => value >= term,
+            Binary::Lt => value < term,
+            Binary::LtEq => value <= term,
+        }
+    }
+
+    /// Parses a boolean, taking 1 and 0 for true and false the way MySQL does.
+    pub fn parse_bool(val: &str) -> Result<bool, String> {
+        match val.to_lowercase().as_ref() {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(format!("{} is not a boolean", val)),
+        }
+    }
+
+    /// Row positions stored in `index` under the keys satisfying `key op val`, in key order.
+    pub fn range<K: Ord + Clone>(
+        index: &BTreeMap<K, Vec<usize>>,
+        val: K,
+        op: &Binary,
+    ) -> Vec<usize> {
+        let bounds = match op {
+            Binary::Eq => (Included(val.clone()), Included(val)),
+            Binary::Gt => (Excluded(val), Unbounded),
+            Binary::GtEq => (Included(val), Unbounded),
+            Binary::Lt => (Unbounded, Excluded(val)),
+            Binary::LtEq => (Unbounded, Included(val)),
+            Binary::NotEq => {
+                return index
+                    .range(..val.clone())
+                    .chain(index.range((Excluded(val), Unbounded)))
+                    .flat_map(|(_key, rows)| rows.iter().copied())
+                    .collect();
+            }
+        };
+        index
+            .range(bounds)
+            .flat_map(|(_key, rows)| rows.iter().copied())
+            .collect()
+    }
+
+    pub fn index_get<'a, K: Ord + Borrow<Q>, Q: Ord + ?Sized>(
+        index: &'a BTreeMap<K, Vec<usize>>,
+        key: &Q,
+    ) -> &'a [usize] {
+        index.get(key).map_or(&[], |rows| rows.as_slice())
+    }
+
+    pub fn index_insert<K: Ord>(index: &mut BTreeMap<K, Vec<usize>>, key: K, row: usize) {
+        let rows = index.entry(key).or_default();
+        if let Err(pos) = rows.binary_search(&row) {
+            rows.insert(pos, row);
+        }
+    }
+
+    pub fn index_remove<K: Ord + Borrow<Q>, Q: Ord + ?Sized>(
+        index: &mut BTreeMap<K, Vec<usize>>,
+        key: &Q,
+        row: usize,
+    ) {
+        if let Some(rows) = index.get_mut(key) {
+            rows.retain(|r| *r != row);
+            if rows.is_empty() {
+                index.remove(key);
+            }
+        }
+    }
+
+    /// Drops the sorted positions in `deleted` from `index` and moves every later position up
+    /// by the number of deleted rows before it.
+    pub fn index_remove_rows<K: Ord>(index: &mut BTreeMap<K, Vec<usize>>, deleted: &[usize]) {
+        index.retain(|_, rows| {
+            rows.retain_mut(|row| match deleted.binary_search(row) {
+                Ok(_) => false,
+                Err(deleted_before) => {
+                    *row -= deleted_before;
+                    true
+                }
+            });
+            !rows.is_empty()
+        });
+    }
+
+    /// Applies `op` to two values, as integers when both are integers, exactly when both are
+    /// numbers written with a decimal point and as floats otherwise. Integer division
+    /// truncates toward zero like MySQL's DIV, so the result still fits an integer column.
+    pub fn arithmetic(left: &str, op: &Arithmetic, right: &str) -> Result<String, String> {
+        if let (Ok(l), Ok(r)) = (left.parse::<i64>(), right.parse::<i64>()) {
+            let result = match op {
+                Arithmetic::Plus => l.checked_add(r),
+                Arithmetic::Minus => l.checked_sub(r),
+                Arithmetic::Multiply => l.checked_mul(r),
+                Arithmetic::Divide => l.checked_div(r),
+                Arithmetic::Modulo => l.checked_rem(r),
+            };
+            return result
+                .map(|v| v.to_string())
+                .ok_or_else(|| format!("cannot compute {} {} {}", left, op, right));
+        }
+        if let Some(result) = decimal::arithmetic(left, op, right) {
+            return result;
+        }
+
+        match (left.parse::<f64>(), right.parse::<f64>()) {
+            (Ok(l), Ok(r)) => {
+                let result = match op {
+                    Arithmetic::Plus => l + r,
+                    Arithmetic::Minus => l - r,
+                    Arithmetic::Multiply => l * r,
+                    Arithmetic::Divide => l / r,
+                    Arithmetic::Modulo => l % r,
+                };
+                match result.is_finite() {
+                    true => Ok(result.to_string()),
+                    false => Err(format!("cannot compute {} {} {}", left, op, right)),
+                }
+            }
+            _ => Err(format!(
+                "cannot compute {} {} {}, both sides must be numbers",
+                left, op, right
+            )),
+        }
+    }
+
+    /// Compares two rendered values, as numbers when both are numbers and as text otherwise.
+    /// Integers compare as i64, which keeps BIGINT values exact.
+    pub fn compare(value: &str, op: &Binary, term: &str) -> bool {
+        let ordering = match (value.parse::<i64>(), term.parse::<i64>()) {
+            (Ok(value), Ok(term)) => Some(value.cmp(&term)),
+            _ => match (value.parse::<f64>(), term.parse::<f64>()) {
+                (Ok(value), Ok(term)) => value.partial_cmp(&term),
+                _ => Some(value.cmp(term)),
+            },
+        };
+        match ordering {
+            Some(ordering) => match op {
+                Binary::Eq => ordering == Ordering::Equal,
+                Binary::NotEq => ordering != Ordering::Equal,
+                Binary::Gt => ordering == Ordering::Greater,
+                Binary::GtEq => ordering != Ordering::Less,
+                Binary::Lt => ordering == Ordering::Less,
+                Binary::LtEq => ordering != Ordering::Greater,
+            },
+            None => false,
+        }
+    }
+
+    pub fn find_first_row<'a>(
+        rows: impl DoubleEndedIterator<Item = &'a usize>,
+        from_end: bool,
+        is_wanted: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        match from_end {
+            true => rows.rev().copied().find(|idx| is_wanted(*idx)),
+            false => rows.copied().find(|idx| is_wanted(*idx)),
+        }
+    }
+
+    // The set operations below work on sorted, deduplicated row positions.
+
+    pub fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+        let mut result = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    result.push(a[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        result
+    }
+
+    pub fn union(a: &[usize], b: &[usize]) -> Vec<usize> {
+        let mut result = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                Ordering::Less => {
+                    result.push(a[i]);
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    result.push(b[j]);
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    result.push(a[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        result.extend_from_slice(&a[i..]);
+        result.extend_from_slice(&b[j..]);
+        result
+    }
+
+    pub fn rotate_2d_vec(data: &[Vec<String>]) -> Vec<Vec<&String>> {
+        match data.first() {
+            None => vec![vec![]],
+            Some(first_col) => {
+                let number_of_rows = first_col.len();
                 let mut ret_data: Vec<Vec<&String>> = vec![vec![]; number_of_rows];
 
-                for row_idx in 0..number_of_rows {
-                    for col_idx in 0..number_of_cols {
-                        ret_data[row_idx].push(&data[col_idx][row_idx]);
+                for (row_idx, row) in ret_data.iter_mut().enumerate() {
+                    for col in data {
+                        row.push(&col[row_idx]);
                     }
                 }
 
-                return ret_data;
+                ret_data
             }
         }
     }
 
-    pub fn pretty_print(data: &Vec<Vec<&String>>, header: &Vec<String>) {
+    pub fn pretty_print(data: &[Vec<&String>], header: &[String]) {
         let mut p_table = PTable::new();
 
         p_table.add_row(Row::new(
@@ -765,7 +4004,7 @@ mod util {
 
         for row in data {
             p_table.add_row(Row::new(
-                row.iter().map(|c| Cell::new(*c)).collect::<Vec<Cell>>(),
+                row.iter().map(|c| Cell::new(c)).collect::<Vec<Cell>>(),
             ));
         }
         p_table.printstd();
@@ -0,0 +1,295 @@
+//! ALTER TABLE on the columns of a table. Every change is checked before anything is
+//! written, so a failing one leaves the table as it was. Foreign keys span tables, the
+//! database keeps them in line with renamed tables and columns.
+
+use super::{Column, ColumnData, ColumnHeader, ColumnIndex, DataType, Table};
+use crate::parser::create::ParsedColumn;
+
+impl Table {
+    /// Appends the column `column`, the rows already in the table take its default value.
+    pub fn add_column(&mut self, column: &ParsedColumn) -> Result<(), String> {
+        if self.column_exist(column.name.to_string()) {
+            return Err(format!(
+                "column {} already exists in table {}",
+                column.name, self.name
+            ));
+        }
+        let header = ColumnHeader::new(
+            column.name.to_string(),
+            column.datatype.to_string(),
+            false,
+            column.is_nullable,
+            column.default.clone(),
+        );
+        if header.datatype == DataType::Invalid {
+            return Err(format!("column {} has an invalid type", column.name));
+        }
+        if header.default.is_none() && !header.is_nullable && self.row_count() > 0 {
+            return Err(format!(
+                "column {} cannot be NULL and has no default value",
+                column.name
+            ));
+        }
+
+        let mut data = Column::new(ColumnData::new(&header.datatype));
+        if let Some(default) = &header.default {
+            data.normalize_value(default)?;
+        }
+        for _ in 0..self.row_count() {
+            data.push_value(header.default.as_deref())?;
+        }
+        self.rows.insert(column.name.to_string(), data);
+        self.columns.push(header);
+        Ok(())
+    }
+
+    /// Removes the column `col_name` along with its values and the indexes over it. A column
+    /// of the primary key or one a constraint looks at cannot go.
+    pub fn drop_column(&mut self, col_name: &str) -> Result<(), String> {
+        self.find_column(col_name)?;
+        if self.columns.len() == 1 {
+            return Err(format!(
+                "cannot drop {}, the only column of table {}",
+                col_name, self.name
+            ));
+        }
+        if self.get_column(col_name.to_string()).is_primary_key
+            || self
+                .indexes
+                .iter()
+                .any(|index| index.is_primary && index.columns.iter().any(|c| c == col_name))
+        {
+            return Err(format!(
+                "cannot drop column {} of the primary key",
+                col_name
+            ));
+        }
+        if let Some(check) = self.checks.iter().find(|check| check.covers(col_name)) {
+            return Err(format!(
+                "cannot drop column {}, check constraint {} uses it",
+                col_name, check.name
+            ));
+        }
+        if let Some(fk) = self.foreign_keys.iter().find(|fk| fk.column == col_name) {
+            return Err(format!(
+                "cannot drop column {}, foreign key {} uses it",
+                col_name, fk.name
+            ));
+        }
+
+        self.indexes
+            .retain(|index| !index.columns.iter().any(|c| c == col_name));
+        self.columns.retain(|c| c.name != col_name);
+        self.rows.remove(col_name);
+        Ok(())
+    }
+
+    pub fn rename_column(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+        self.find_column(old_name)?;
+        if old_name == new_name {
+            return Ok(());
+        }
+        if self.column_exist(new_name.to_string()) {
+            return Err(format!(
+                "column {} already exists in table {}",
+                new_name, self.name
+            ));
+        }
+        // The condition of a CHECK constraint is kept as written, it cannot follow the rename.
+        if let Some(check) = self.checks.iter().find(|check| check.covers(old_name)) {
+            return Err(format!(
+                "cannot rename column {}, check constraint {} uses it",
+                old_name, check.name
+            ));
+        }
+
+        let data = self.rows.remove(old_name).unwrap();
+        self.rows.insert(new_name.to_string(), data);
+        for header in self.columns.iter_mut().filter(|c| c.name == old_name) {
+            header.name = new_name.to_string();
+        }
+        for index in &mut self.indexes {
+            for col_name in index.columns.iter_mut().filter(|c| *c == old_name) {
+                *col_name = new_name.to_string();
+            }
+        }
+        for fk in self
+            .foreign_keys
+            .iter_mut()
+            .filter(|fk| fk.column == old_name)
+        {
+            fk.column = new_name.to_string();
+        }
+        Ok(())
+    }
+
+    /// Gives the column `col_name` the type `datatype`, converting its values. A value that
+    /// doesn't survive the conversion unchanged, like 1.5 to an INT, is an error.
+    pub fn change_column_type(&mut self, col_name: &str, datatype: &str) -> Result<(), String> {
+        self.find_column(col_name)?;
+        let header = self.get_column(col_name.to_string());
+        let new_type = DataType::new(datatype.to_string());
+        if new_type == DataType::Invalid {
+            return Err(format!(
+                "cannot change column {} to an invalid type",
+                col_name
+            ));
+        }
+        if let Some(fk) = self.foreign_keys.iter().find(|fk| fk.column == col_name) {
+            return Err(format!(
+                "cannot change the type of column {}, foreign key {} uses it",
+                col_name, fk.name
+            ));
+        }
+        if header.auto_increment.is_some() && !new_type.is_integer() {
+            return Err(format!(
+                "AUTO_INCREMENT column {} must be an integer, not {}",
+                col_name, new_type
+            ));
+        }
+
+        let old_data = self.rows.get(col_name).unwrap();
+        let mut data = Column::new(ColumnData::new(&new_type));
+        let is_number = is_numeric(&header.datatype) && is_numeric(&new_type);
+        let convert = |val: &str| {
+            // Numbers are compared by value, 3.0 goes into an INT as 3.
+            let source = match is_number {
+                true => trim_number(val),
+                false => val,
+            };
+            let converted = data.normalize_value(source).map_err(|_| {
+                format!(
+                    "cannot convert {} of column {} to {}",
+                    val, col_name, new_type
+                )
+            })?;
+            let is_kept = match is_number {
+                true => trim_number(&converted) == source,
+                false => old_data
+                    .normalize_value(&converted)
+                    .is_ok_and(|back| back == val),
+            };
+            match is_kept {
+                true => Ok(converted),
+                false => Err(format!(
+                    "converting {} of column {} to {} loses data",
+                    val, col_name, new_type
+                )),
+            }
+        };
+        let default = header.default.as_deref().map(convert).transpose()?;
+        let mut values = vec![];
+        for row in 0..self.row_count() {
+            values.push(
+                old_data
+                    .get_value(row)
+                    .as_deref()
+                    .map(convert)
+                    .transpose()?,
+            );
+        }
+        for val in &values {
+            data.push_value(val.as_deref())?;
+        }
+
+        let mut index = ColumnIndex::new(&new_type);
+        if header.is_indexed {
+            for (row, val) in values.iter().enumerate() {
+                if let Some(val) = val {
+                    if header.is_unique && !index.get_idx_data(val)?.is_empty() {
+                        return Err(header.unique_violation(val));
+                    }
+                    index.insert_value(val, row)?;
+                }
+            }
+        }
+
+        let old = self.replace_column(col_name, new_type, index, default, data);
+        if let Err(err) = self.rebuild_after_type_change(col_name) {
+            let (datatype, index, default, data) = old;
+            self.replace_column(col_name, datatype, index, default, data);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    fn find_column(&self, col_name: &str) -> Result<(), String> {
+        match self.column_exist(col_name.to_string()) {
+            true => Ok(()),
+            false => Err(format!(
+                "cannot find column {} in table {}",
+                col_name, self.name
+            )),
+        }
+    }
+
+    /// Puts the given type, index, default value and values in place of those of the column
+    /// `col_name`, returning the ones it had.
+    fn replace_column(
+        &mut self,
+        col_name: &str,
+        datatype: DataType,
+        index: ColumnIndex,
+        default: Option<String>,
+        data: Column,
+    ) -> (DataType, ColumnIndex, Option<String>, Column) {
+        let header = self
+            .columns
+            .iter_mut()
+            .find(|c| c.name == col_name)
+            .unwrap();
+        (
+            std::mem::replace(&mut header.datatype, datatype),
+            std::mem::replace(&mut header.index, index),
+            std::mem::replace(&mut header.default, default),
+            self.rows.insert(col_name.to_string(), data).unwrap(),
+        )
+    }
+
+    /// Rebuilds the multi-column indexes over the column `col_name` once its values changed
+    /// type and checks every row against the CHECK constraints looking at it.
+    fn rebuild_after_type_change(&mut self, col_name: &str) -> Result<(), String> {
+        let mut rebuilt = vec![];
+        for (pos, index) in self.indexes.iter().enumerate() {
+            if index.columns.iter().any(|c| c == col_name) {
+                rebuilt.push((pos, index.name.to_string(), index.columns.clone()));
+            }
+        }
+        let mut indexes = vec![];
+        for (pos, name, columns) in rebuilt {
+            let is_unique = self.indexes[pos].is_unique;
+            let mut index = self.create_composite_index(&name, &columns, is_unique)?;
+            index.is_primary = self.indexes[pos].is_primary;
+            indexes.push((pos, index));
+        }
+
+        for check in self.checks.iter().filter(|check| check.covers(col_name)) {
+            self.validate_check(&check.name, &check.predicate)?;
+        }
+        for row in 0..self.row_count() {
+            let values = self
+                .columns
+                .iter()
+                .map(|header| self.get_value(&header.name, row))
+                .collect::<Vec<Option<String>>>();
+            self.check_row(&values)?;
+        }
+
+        for (pos, index) in indexes {
+            self.indexes[pos] = index;
+        }
+        Ok(())
+    }
+}
+
+fn is_numeric(datatype: &DataType) -> bool {
+    datatype.is_integer() || matches!(datatype, DataType::Float | DataType::Decimal(_))
+}
+
+/// A number without the zeros ending its fraction, 3.50 is 3.5 and 3.00 is 3.
+fn trim_number(val: &str) -> &str {
+    match val.contains('.') {
+        true => val.trim_end_matches('0').trim_end_matches('.'),
+        false => val,
+    }
+}
@@ -0,0 +1,440 @@
+//! Indexes over several columns, keyed on the tuple of their values in column order. A tuple
+//! holding a NULL is left out of the index, the same way single column indexes skip NULLs.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use super::{temporal, util, Comparison, DataType, FloatKey, Table};
+use crate::parser::select::{Binary, Operator, Predicate};
+
+/// Position of an index in `Table::indexes` along with the new tuple of each updated row,
+/// `None` where the tuple holds a NULL.
+type IndexUpdate = (usize, Vec<Option<Vec<IndexKey>>>);
+
+/// Rows found through an index, along with the conjuncts the index answered.
+type IndexLookup = (Vec<usize>, Vec<usize>);
+
+/// A value parsed according to the type of its column, ordered the way the column sorts.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub enum IndexKey {
+    Int(i64),
+    Str(String),
+    Float(FloatKey),
+    Decimal(i128),
+    Bool(bool),
+    Date(i32),
+    Time(i32),
+    DateTime(i64),
+}
+
+impl IndexKey {
+    pub(super) fn parse(datatype: &DataType, val: &str) -> Result<IndexKey, String> {
+        Ok(match datatype {
+            DataType::TinyInt | DataType::SmallInt | DataType::Int | DataType::BigInt => {
+                IndexKey::Int(val.parse::<i64>().map_err(|e| e.to_string())?)
+            }
+            DataType::Str => IndexKey::Str(val.to_string()),
+            DataType::Float => IndexKey::Float(FloatKey::parse(val)?),
+            DataType::Decimal(decimal) => IndexKey::Decimal(decimal.parse(val)?),
+            DataType::Bool => IndexKey::Bool(util::parse_bool(val)?),
+            DataType::Date => IndexKey::Date(temporal::parse_date(val)?),
+            DataType::Time => IndexKey::Time(temporal::parse_time(val)?),
+            DataType::DateTime | DataType::Timestamp => {
+                IndexKey::DateTime(temporal::parse_datetime(val)?)
+            }
+            DataType::Invalid => return Err(format!("cannot index {}", val)),
+        })
+    }
+
+    /// Like `parse` for the term of a where clause. A DECIMAL term isn't rounded to the scale
+    /// of its column, it has no key when no stored value can equal it.
+    pub(super) fn parse_term(datatype: &DataType, val: &str) -> Result<Option<IndexKey>, String> {
+        match datatype {
+            DataType::Decimal(decimal) => match decimal.comparison(&Binary::Eq, val)? {
+                Comparison::Term(_, key) => Ok(Some(IndexKey::Decimal(key))),
+                _ => Ok(None),
+            },
+            _ => IndexKey::parse(datatype, val).map(Some),
+        }
+    }
+
+    /// Whether the stored value `val` satisfies `val op term`, comparing a DECIMAL at the full
+    /// precision of the term.
+    pub(super) fn satisfies(
+        datatype: &DataType,
+        val: &str,
+        op: &Binary,
+        term: &str,
+    ) -> Result<bool, String> {
+        match datatype {
+            DataType::Decimal(decimal) => {
+                Ok(decimal.comparison(op, term)?.matches(decimal.parse(val)?))
+            }
+            _ => Ok(util::satisfies(
+                &IndexKey::parse(datatype, val)?,
+                op,
+                &IndexKey::parse(datatype, term)?,
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CompositeIndex {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    entries: BTreeMap<Vec<IndexKey>, Vec<usize>>,
+}
+
+impl CompositeIndex {
+    pub fn new(name: &str, columns: &[String], is_unique: bool) -> CompositeIndex {
+        CompositeIndex {
+            name: name.to_string(),
+            columns: columns.to_vec(),
+            is_unique,
+            is_primary: false,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    fn get(&self, key: &[IndexKey]) -> &[usize] {
+        util::index_get(&self.entries, key)
+    }
+
+    fn insert(&mut self, key: Vec<IndexKey>, row: usize) {
+        util::index_insert(&mut self.entries, key, row);
+    }
+
+    fn remove(&mut self, key: &[IndexKey], row: usize) {
+        util::index_remove(&mut self.entries, key, row);
+    }
+
+    pub fn remove_rows(&mut self, deleted: &[usize]) {
+        util::index_remove_rows(&mut self.entries, deleted);
+    }
+
+    /// Rows whose tuple starts with `prefix`, and whose next value also satisfies `next` when
+    /// it is given. Sorted by row position.
+    fn prefix_rows(&self, prefix: &[IndexKey], next: Option<(&Binary, IndexKey)>) -> Vec<usize> {
+        let depth = prefix.len();
+        let mut start = prefix.to_vec();
+        if let Some((Binary::Eq | Binary::Gt | Binary::GtEq, key)) = &next {
+            start.push(key.clone());
+        }
+        let mut rows = self
+            .entries
+            .range(start..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .take_while(|(key, _)| match &next {
+                Some((Binary::Eq, val)) | Some((Binary::LtEq, val)) => key[depth] <= *val,
+                Some((Binary::Lt, val)) => key[depth] < *val,
+                _ => true,
+            })
+            .filter(|(key, _)| match &next {
+                Some((op, val)) => util::satisfies(&key[depth], op, val),
+                None => true,
+            })
+            .flat_map(|(_, rows)| rows.iter().copied())
+            .collect::<Vec<usize>>();
+        rows.sort_unstable();
+        rows
+    }
+}
+
+impl Table {
+    /// The tuple `index` files `values` under, `values` being given in the order of the
+    /// columns of the index. `None` when one of them is NULL.
+    fn composite_key(
+        &self,
+        index: &CompositeIndex,
+        values: &[Option<String>],
+    ) -> Result<Option<Vec<IndexKey>>, String> {
+        let mut key = vec![];
+        for (col_name, val) in index.columns.iter().zip(values) {
+            match val {
+                Some(val) => {
+                    let datatype = &self.get_column(col_name.to_string()).datatype;
+                    key.push(IndexKey::parse(datatype, val)?);
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(key))
+    }
+
+    /// The values the row at `row` holds in the columns of `index`.
+    fn composite_row_values(&self, index: &CompositeIndex, row: usize) -> Vec<Option<String>> {
+        index
+            .columns
+            .iter()
+            .map(|col_name| self.rows.get(col_name).unwrap().get_value(row))
+            .collect()
+    }
+
+    /// The tuple `index` files the row at `row` under.
+    fn composite_row_key(
+        &self,
+        index: &CompositeIndex,
+        row: usize,
+    ) -> Result<Option<Vec<IndexKey>>, String> {
+        self.composite_key(index, &self.composite_row_values(index, row))
+    }
+
+    /// Builds a multi-column index over the rows already in the table.
+    pub(super) fn create_composite_index(
+        &mut self,
+        name: &str,
+        columns: &[String],
+        is_unique: bool,
+    ) -> Result<CompositeIndex, String> {
+        let mut index = CompositeIndex::new(name, columns, is_unique);
+        for row in 0..self.row_count() {
+            let values = self.composite_row_values(&index, row);
+            if let Some(key) = self.composite_key(&index, &values)? {
+                if is_unique && !index.get(&key).is_empty() {
+                    return Err(unique_violation(&index, &values));
+                }
+                index.insert(key, row);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Fails when a unique multi-column index already holds the tuple a new row with
+    /// `values` would get, `cols` naming the column of each value.
+    pub(super) fn check_composite_insert(
+        &self,
+        cols: &[String],
+        values: &[Option<String>],
+    ) -> Result<(), String> {
+        for index in self.indexes.iter().filter(|index| index.is_unique) {
+            let values = match index
+                .columns
+                .iter()
+                .map(|col_name| cols.iter().position(|c| c == col_name))
+                .collect::<Option<Vec<usize>>>()
+            {
+                Some(positions) => positions
+                    .into_iter()
+                    .map(|idx| values[idx].clone())
+                    .collect::<Vec<Option<String>>>(),
+                None => continue,
+            };
+            if let Some(key) = self.composite_key(index, &values)? {
+                if !index.get(&key).is_empty() {
+                    return Err(unique_violation(index, &values));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails when two of the new rows `rows`, holding a value for every column, share the
+    /// tuple of a unique multi-column index.
+    pub(super) fn check_composite_batch(&self, rows: &[Vec<Option<String>>]) -> Result<(), String> {
+        for index in self.indexes.iter().filter(|index| index.is_unique) {
+            let positions = index
+                .columns
+                .iter()
+                .map(|col_name| {
+                    self.columns
+                        .iter()
+                        .position(|c| c.name == *col_name)
+                        .unwrap()
+                })
+                .collect::<Vec<usize>>();
+            let mut keys = BTreeSet::new();
+            for row in rows {
+                let values = positions
+                    .iter()
+                    .map(|pos| row[*pos].clone())
+                    .collect::<Vec<Option<String>>>();
+                if let Some(key) = self.composite_key(index, &values)? {
+                    if !keys.insert(key) {
+                        return Err(unique_violation(index, &values));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Files the row at `row` in every multi-column index.
+    pub(super) fn insert_composite_keys(&mut self, row: usize) -> Result<(), String> {
+        let mut keys = vec![];
+        for index in &self.indexes {
+            keys.push(self.composite_row_key(index, row)?);
+        }
+        for (index, key) in self.indexes.iter_mut().zip(keys) {
+            if let Some(key) = key {
+                index.insert(key, row);
+            }
+        }
+        Ok(())
+    }
+
+    /// The tuples of `rows` in each multi-column index covering one of `columns`, once
+    /// `columns` take `new_values`. Fails when that breaks a unique index.
+    pub(super) fn composite_keys_after_update(
+        &self,
+        rows: &[usize],
+        columns: &[String],
+        new_values: &[Vec<Option<String>>],
+    ) -> Result<Vec<IndexUpdate>, String> {
+        let mut updates = vec![];
+        let updated_rows = rows.iter().collect::<HashSet<&usize>>();
+        for (i, index) in self.indexes.iter().enumerate() {
+            if !index.columns.iter().any(|c| columns.contains(c)) {
+                continue;
+            }
+            let mut keys = vec![];
+            let mut new_keys = BTreeSet::new();
+            for (n, row) in rows.iter().enumerate() {
+                let values = index
+                    .columns
+                    .iter()
+                    .map(
+                        |col_name| match columns.iter().position(|c| c == col_name) {
+                            Some(assigned) => new_values[assigned][n].clone(),
+                            None => self.rows.get(col_name).unwrap().get_value(*row),
+                        },
+                    )
+                    .collect::<Vec<Option<String>>>();
+                let key = self.composite_key(index, &values)?;
+                if let (true, Some(key)) = (index.is_unique, &key) {
+                    let is_taken = index.get(key).iter().any(|row| !updated_rows.contains(row));
+                    if is_taken || !new_keys.insert(key.clone()) {
+                        return Err(unique_violation(index, &values));
+                    }
+                }
+                keys.push(key);
+            }
+            updates.push((i, keys));
+        }
+        Ok(updates)
+    }
+
+    /// Replaces the tuples of `rows` with the ones `composite_keys_after_update` worked out.
+    /// Runs before the new values are written, the old tuples are read off the rows.
+    pub(super) fn update_composite_keys(
+        &mut self,
+        rows: &[usize],
+        updates: Vec<IndexUpdate>,
+    ) -> Result<(), String> {
+        for (i, keys) in updates {
+            let mut old_keys = vec![];
+            for row in rows {
+                old_keys.push(self.composite_row_key(&self.indexes[i], *row)?);
+            }
+            let index = &mut self.indexes[i];
+            for (row, old_key) in rows.iter().zip(old_keys) {
+                if let Some(old_key) = old_key {
+                    index.remove(&old_key, *row);
+                }
+            }
+            for (row, key) in rows.iter().zip(keys) {
+                if let Some(key) = key {
+                    index.insert(key, *row);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers the conjuncts of a where clause from a multi-column index when they fix the
+    /// leading columns of one with `=`, optionally followed by a range on the next column.
+    /// Returns the matching rows and the positions of the conjuncts the index took care of.
+    pub(super) fn composite_index_lookup(
+        &self,
+        conjuncts: &[&Predicate],
+    ) -> Result<Option<IndexLookup>, String> {
+        let mut best: Option<IndexLookup> = None;
+        for index in &self.indexes {
+            let mut prefix = vec![];
+            let mut next = None;
+            let mut used = vec![];
+            for col_name in &index.columns {
+                let datatype = &self.get_column(col_name.to_string()).datatype;
+                next = None;
+                for (i, conjunct) in conjuncts.iter().enumerate() {
+                    let expr = match conjunct {
+                        Predicate::Expression(expr) if expr.aggregate.is_none() => expr,
+                        _ => continue,
+                    };
+                    let Operator::Binary(op) = &expr.op;
+                    if expr.left != *col_name || *op == Binary::NotEq {
+                        continue;
+                    }
+                    let key = match IndexKey::parse_term(datatype, &expr.right)? {
+                        Some(key) => key,
+                        // Left to the scan, which compares it exactly.
+                        None => continue,
+                    };
+                    if *op == Binary::Eq {
+                        next = Some((op, key, i));
+                        break;
+                    }
+                    if next.is_none() {
+                        next = Some((op, key, i));
+                    }
+                }
+                match next.take() {
+                    Some((Binary::Eq, key, i)) => {
+                        prefix.push(key);
+                        used.push(i);
+                    }
+                    range => {
+                        next = range;
+                        break;
+                    }
+                }
+            }
+            let next = next.map(|(op, key, i)| {
+                used.push(i);
+                (op, key)
+            });
+
+            // A lone leading column is better served by its own index when it has one.
+            let is_useful = match used.len() {
+                0 => false,
+                1 => !self.get_column(index.columns[0].to_string()).is_indexed,
+                _ => true,
+            };
+            let is_better = best
+                .as_ref()
+                .is_none_or(|(_, best_used)| used.len() > best_used.len());
+            if is_useful && is_better {
+                best = Some((index.prefix_rows(&prefix, next), used));
+            }
+        }
+        Ok(best)
+    }
+}
+
+/// The predicates `predicate` ANDs together.
+pub(super) fn conjuncts(predicate: &Predicate) -> Vec<&Predicate> {
+    match predicate {
+        Predicate::And(left, right) => {
+            let mut all = conjuncts(left);
+            all.extend(conjuncts(right));
+            all
+        }
+        _ => vec![predicate],
+    }
+}
+
+fn unique_violation(index: &CompositeIndex, values: &[Option<String>]) -> String {
+    let values = values
+        .iter()
+        .map(|val| val.as_deref().unwrap_or("NULL"))
+        .collect::<Vec<&str>>()
+        .join(", ");
+    format!(
+        "Error: unique constraint violation for index {}. Value ({}) already exists for columns ({})",
+        index.name,
+        values,
+        index.columns.join(", ")
+    )
+}
@@ -0,0 +1,212 @@
+//! CHECK constraints and foreign keys, kept with the table. CHECK constraints are tested on
+//! every row an INSERT or UPDATE writes. Like in SQL a row only breaks one when its condition
+//! is false, a condition that is unknown because of a NULL lets the row through. Foreign keys
+//! span two tables, the database enforces them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::{composite::IndexKey, Table};
+use crate::parser::{
+    create::{ForeignKeyAction, ParsedCheck},
+    select::{Operator, Predicate},
+};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CheckConstraint {
+    pub name: String,
+    pub expr: String,
+    pub(super) predicate: Predicate,
+}
+
+impl CheckConstraint {
+    /// True when the condition looks at the column `col_name`.
+    pub fn covers(&self, col_name: &str) -> bool {
+        fn covers(predicate: &Predicate, col_name: &str) -> bool {
+            match predicate {
+                Predicate::And(left, right) | Predicate::Or(left, right) => {
+                    covers(left, col_name) || covers(right, col_name)
+                }
+                Predicate::Not(inner) => covers(inner, col_name),
+                Predicate::Expression(expr) => expr.left == col_name,
+                Predicate::IsNull(name) | Predicate::IsNotNull(name) => name == col_name,
+            }
+        }
+        covers(&self.predicate, col_name)
+    }
+}
+
+/// The column `column` only holds NULL or values found in `parent_column` of `parent_table`.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ForeignKey {
+    pub name: String,
+    pub column: String,
+    pub parent_table: String,
+    pub parent_column: String,
+    pub on_delete: ForeignKeyAction,
+    pub on_update: ForeignKeyAction,
+}
+
+impl Table {
+    /// True when some row holds `val` in the indexed column `col_name`.
+    pub fn has_key(&self, col_name: &str, val: &str) -> Result<bool, String> {
+        let header = self.get_column(col_name.to_string());
+        Ok(!header.index.get_idx_data(val)?.is_empty())
+    }
+
+    /// Sorted positions of the rows holding one of `keys` in the column `col_name`.
+    pub fn rows_holding(&self, col_name: &str, keys: &HashSet<String>) -> Vec<usize> {
+        (0..self.row_count())
+            .filter(|row| {
+                self.get_value(col_name, *row)
+                    .is_some_and(|val| keys.contains(&val))
+            })
+            .collect()
+    }
+
+    /// Adds the CHECK constraint `check`, which may only compare columns of the table with
+    /// values that fit them.
+    pub(super) fn add_check(&mut self, check: &ParsedCheck) -> Result<(), String> {
+        if self.checks.iter().any(|c| c.name == check.name) {
+            return Err(format!("Duplicate check constraint name {}", check.name));
+        }
+        self.validate_check(&check.name, &check.predicate)?;
+        self.checks.push(CheckConstraint {
+            name: check.name.to_string(),
+            expr: check.expr.to_string(),
+            predicate: check.predicate.clone(),
+        });
+        Ok(())
+    }
+
+    pub(super) fn validate_check(&self, name: &str, predicate: &Predicate) -> Result<(), String> {
+        match predicate {
+            Predicate::And(left, right) | Predicate::Or(left, right) => {
+                self.validate_check(name, left)?;
+                self.validate_check(name, right)
+            }
+            Predicate::Not(inner) => self.validate_check(name, inner),
+            Predicate::Expression(expr) => {
+                if expr.aggregate.is_some() {
+                    return Err(format!(
+                        "check constraint {} cannot use {}",
+                        name, expr.left
+                    ));
+                }
+                if !self.column_exist(expr.left.to_string()) {
+                    return Err(format!(
+                        "check constraint {} refers to unknown column {}",
+                        name, expr.left
+                    ));
+                }
+                let datatype = &self.get_column(expr.left.to_string()).datatype;
+                IndexKey::parse_term(datatype, &expr.right).map_err(|_| {
+                    format!(
+                        "check constraint {} cannot compare column {} with {}",
+                        name, expr.left, expr.right
+                    )
+                })?;
+                Ok(())
+            }
+            Predicate::IsNull(col_name) | Predicate::IsNotNull(col_name) => {
+                match self.column_exist(col_name.to_string()) {
+                    true => Ok(()),
+                    false => Err(format!(
+                        "check constraint {} refers to unknown column {}",
+                        name, col_name
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Fails when the row holding `values`, one per column in table order, breaks a CHECK
+    /// constraint.
+    pub(super) fn check_row(&self, values: &[Option<String>]) -> Result<(), String> {
+        for check in &self.checks {
+            if self.evaluate_check(&check.predicate, values)? == Some(false) {
+                return Err(format!(
+                    "Error: check constraint {} is violated by ({})",
+                    check.name,
+                    values
+                        .iter()
+                        .map(|val| val.as_deref().unwrap_or("NULL"))
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `check_row` for each of `rows` once `columns` take `new_values`.
+    pub(super) fn check_updated_rows(
+        &self,
+        rows: &[usize],
+        columns: &[String],
+        new_values: &[Vec<Option<String>>],
+    ) -> Result<(), String> {
+        if self.checks.is_empty() {
+            return Ok(());
+        }
+        for (n, row) in rows.iter().enumerate() {
+            let values = self
+                .columns
+                .iter()
+                .map(
+                    |header| match columns.iter().position(|c| *c == header.name) {
+                        Some(assigned) => new_values[assigned][n].clone(),
+                        None => self.rows.get(&header.name).unwrap().get_value(*row),
+                    },
+                )
+                .collect::<Vec<Option<String>>>();
+            self.check_row(&values)?;
+        }
+        Ok(())
+    }
+
+    /// Evaluates `predicate` on a row, `None` standing for unknown.
+    fn evaluate_check(
+        &self,
+        predicate: &Predicate,
+        values: &[Option<String>],
+    ) -> Result<Option<bool>, String> {
+        let value = |col_name: &str| {
+            let idx = self
+                .columns
+                .iter()
+                .position(|c| c.name == col_name)
+                .unwrap();
+            &values[idx]
+        };
+        Ok(match predicate {
+            Predicate::And(left, right) => match (
+                self.evaluate_check(left, values)?,
+                self.evaluate_check(right, values)?,
+            ) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            Predicate::Or(left, right) => match (
+                self.evaluate_check(left, values)?,
+                self.evaluate_check(right, values)?,
+            ) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+            Predicate::Not(inner) => self.evaluate_check(inner, values)?.map(|holds| !holds),
+            Predicate::Expression(expr) => match value(&expr.left) {
+                Some(val) => {
+                    let datatype = &self.get_column(expr.left.to_string()).datatype;
+                    let Operator::Binary(op) = &expr.op;
+                    Some(IndexKey::satisfies(datatype, val, op, &expr.right)?)
+                }
+                None => None,
+            },
+            Predicate::IsNull(col_name) => Some(value(col_name).is_none()),
+            Predicate::IsNotNull(col_name) => Some(value(col_name).is_some()),
+        })
+    }
+}
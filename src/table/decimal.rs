@@ -0,0 +1,252 @@
+//! DECIMAL values are stored as integers counting units of their last decimal place, with a
+//! scale of 2 `12.34` is stored as 1234.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::parser::{select::Binary, update::Arithmetic};
+
+/// Fraction digits AVG adds to the scale of the column it averages, like MySQL does.
+const AVG_EXTRA_SCALE: u32 = 4;
+
+/// A where clause comparison on stored DECIMAL values, once its term is brought to the scale
+/// of the column without rounding it.
+#[derive(Debug, PartialEq)]
+pub enum Comparison {
+    Term(Binary, i128),
+    All,     // every value satisfies it
+    Nothing, // no value does
+}
+
+impl Comparison {
+    pub fn matches(&self, value: i128) -> bool {
+        match self {
+            Comparison::Term(op, term) => match op {
+                Binary::Eq => value == *term,
+                Binary::NotEq => value != *term,
+                Binary::Gt => value > *term,
+                Binary::GtEq => value >= *term,
+                Binary::Lt => value < *term,
+                Binary::LtEq => value <= *term,
+            },
+            Comparison::All => true,
+            Comparison::Nothing => false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub struct Decimal {
+    pub precision: u32,
+    pub scale: u32,
+}
+
+impl Decimal {
+    /// Largest precision whose values always fit an `i128`.
+    pub const MAX_PRECISION: u32 = 38;
+
+    /// Parses `val`, rounding fraction digits past the scale half away from zero. Values with
+    /// more integer digits than the precision leaves room for are an error.
+    pub fn parse(&self, val: &str) -> Result<i128, String> {
+        let (is_negative, int_digits, frac_digits) =
+            split_number(val).ok_or_else(|| format!("{} is not a valid decimal", val))?;
+
+        let scale = self.scale as usize;
+        let mut value: i128 = 0;
+        for c in int_digits.trim_start_matches('0').chars().chain(
+            frac_digits
+                .chars()
+                .chain(std::iter::repeat('0'))
+                .take(scale),
+        ) {
+            value = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add(c.to_digit(10).unwrap() as i128))
+                .ok_or_else(|| self.out_of_range(val))?;
+        }
+        if frac_digits
+            .as_bytes()
+            .get(scale)
+            .is_some_and(|d| *d >= b'5')
+        {
+            value = value.checked_add(1).ok_or_else(|| self.out_of_range(val))?;
+        }
+        if value >= 10i128.pow(self.precision) {
+            return Err(self.out_of_range(val));
+        }
+        Ok(if is_negative { -value } else { value })
+    }
+
+    /// How `value op val` reads on the stored values of this type. A term with fraction
+    /// digits past the scale lies between two stored values and one too large for an `i128`
+    /// lies past all of them, neither is rounded or rejected.
+    pub fn comparison(&self, op: &Binary, val: &str) -> Result<Comparison, String> {
+        let (is_negative, int_digits, frac_digits) =
+            split_number(val).ok_or_else(|| format!("{} is not a valid decimal", val))?;
+        let scale = self.scale as usize;
+        let mut magnitude = Some(0i128);
+        for c in int_digits.chars().chain(
+            frac_digits
+                .chars()
+                .chain(std::iter::repeat('0'))
+                .take(scale),
+        ) {
+            magnitude = magnitude
+                .and_then(|m| m.checked_mul(10))
+                .and_then(|m| m.checked_add(c.to_digit(10).unwrap() as i128));
+        }
+        let magnitude = match magnitude {
+            Some(magnitude) => magnitude,
+            None => {
+                return Ok(match (op, is_negative) {
+                    (Binary::NotEq, _)
+                    | (Binary::Lt | Binary::LtEq, false)
+                    | (Binary::Gt | Binary::GtEq, true) => Comparison::All,
+                    _ => Comparison::Nothing,
+                })
+            }
+        };
+        if frac_digits.chars().skip(scale).all(|c| c == '0') {
+            let term = if is_negative { -magnitude } else { magnitude };
+            return Ok(Comparison::Term(op.clone(), term));
+        }
+
+        // The term lies strictly between `floor` and the stored value right above it.
+        let floor = if is_negative {
+            -magnitude - 1
+        } else {
+            magnitude
+        };
+        Ok(match op {
+            Binary::Eq => Comparison::Nothing,
+            Binary::NotEq => Comparison::All,
+            Binary::Gt | Binary::GtEq => Comparison::Term(Binary::Gt, floor),
+            Binary::Lt | Binary::LtEq => Comparison::Term(Binary::LtEq, floor),
+        })
+    }
+
+    fn out_of_range(&self, val: &str) -> String {
+        format!("{} is out of range for {}", val, self)
+    }
+
+    pub fn format(&self, value: i128) -> String {
+        let scale = self.scale as usize;
+        let digits = format!("{:0>width$}", value.unsigned_abs(), width = scale + 1);
+        let (int_digits, frac_digits) = digits.split_at(digits.len() - scale);
+        let sign = if value < 0 { "-" } else { "" };
+        match scale {
+            0 => format!("{}{}", sign, int_digits),
+            _ => format!("{}{}.{}", sign, int_digits, frac_digits),
+        }
+    }
+
+    /// Renders `sum / count` with a few more fraction digits than the column has.
+    pub fn format_average(&self, sum: i128, count: i128) -> Result<String, String> {
+        let scaled = sum
+            .checked_mul(10i128.pow(AVG_EXTRA_SCALE))
+            .ok_or_else(|| format!("cannot compute the average of {}", self.format(sum)))?;
+        let mut quotient = scaled / count;
+        if (scaled % count).abs() * 2 >= count {
+            quotient += scaled.signum();
+        }
+        Ok(self.average_type().format(quotient))
+    }
+
+    /// The type of the AVG of a column of this type.
+    pub fn average_type(&self) -> Decimal {
+        Decimal {
+            precision: Decimal::MAX_PRECISION,
+            scale: self.scale + AVG_EXTRA_SCALE,
+        }
+    }
+}
+
+/// The sign, integer digits and fraction digits of `val`, `None` when it isn't a number
+/// written with digits and an optional decimal point.
+fn split_number(val: &str) -> Option<(bool, &str, &str)> {
+    let (is_negative, digits) = match val.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, val.strip_prefix('+').unwrap_or(val)),
+    };
+    let (int_digits, frac_digits) = digits.split_once('.').unwrap_or((digits, ""));
+    let is_number = !(int_digits.is_empty() && frac_digits.is_empty())
+        && int_digits
+            .chars()
+            .chain(frac_digits.chars())
+            .all(|c| c.is_ascii_digit());
+    is_number.then_some((is_negative, int_digits, frac_digits))
+}
+
+/// `val` as a count of units of its last fraction digit, along with the number of fraction
+/// digits. `None` when it isn't a number or doesn't fit an `i128`.
+fn parse_exact(val: &str) -> Option<(i128, u32)> {
+    let (is_negative, int_digits, frac_digits) = split_number(val)?;
+    let mut value: i128 = 0;
+    for c in int_digits.chars().chain(frac_digits.chars()) {
+        value = value
+            .checked_mul(10)?
+            .checked_add(c.to_digit(10).unwrap() as i128)?;
+    }
+    Some((
+        if is_negative { -value } else { value },
+        frac_digits.len() as u32,
+    ))
+}
+
+/// Applies `op` to two numbers exactly, the way DECIMAL values are computed. A quotient gets
+/// a few more fraction digits than the dividend, like in MySQL. Returns `None` when one side
+/// isn't a number that fits an `i128`, leaving it to floating point.
+pub fn arithmetic(left: &str, op: &Arithmetic, right: &str) -> Option<Result<String, String>> {
+    let (l, l_scale) = parse_exact(left)?;
+    let (r, r_scale) = parse_exact(right)?;
+    let rescale = |value: i128, from: u32, to: u32| {
+        10i128
+            .checked_pow(to - from)
+            .and_then(|factor| value.checked_mul(factor))
+    };
+    let scale = l_scale.max(r_scale);
+    let result = match op {
+        Arithmetic::Plus | Arithmetic::Minus | Arithmetic::Modulo => {
+            match (rescale(l, l_scale, scale), rescale(r, r_scale, scale)) {
+                (Some(l), Some(r)) => match op {
+                    Arithmetic::Plus => l.checked_add(r),
+                    Arithmetic::Minus => l.checked_sub(r),
+                    _ => l.checked_rem(r),
+                }
+                .map(|value| (value, scale)),
+                _ => None,
+            }
+        }
+        Arithmetic::Multiply => l.checked_mul(r).map(|value| (value, l_scale + r_scale)),
+        Arithmetic::Divide => rescale(l, 0, r_scale + AVG_EXTRA_SCALE).and_then(|scaled| {
+            let mut quotient = scaled.checked_div(r)?;
+            if (scaled % r).unsigned_abs() * 2 >= r.unsigned_abs() {
+                quotient += scaled.signum() * r.signum();
+            }
+            Some((quotient, l_scale + AVG_EXTRA_SCALE))
+        }),
+    };
+    Some(match result {
+        Some((value, scale)) => {
+            let decimal = Decimal {
+                precision: Decimal::MAX_PRECISION,
+                scale,
+            };
+            let formatted = decimal.format(value);
+            Ok(match scale {
+                0 => formatted,
+                _ => formatted
+                    .trim_end_matches('0')
+                    .trim_end_matches('.')
+                    .to_string(),
+            })
+        }
+        None => Err(format!("cannot compute {} {} {}", left, op, right)),
+    })
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Decimal({},{})", self.precision, self.scale)
+    }
+}
@@ -0,0 +1,369 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::{ColumnIndex, QueryResult, Table};
+use crate::parser::select::{
+    Expression, Join, JoinKind, OrderBy, Predicate, Projection, SelectQuery,
+};
+
+/// A table taking part in a join, `name` is its alias when it was given one.
+struct Source<'a> {
+    table: &'a Table,
+    name: String,
+}
+
+/// The tables of a select with joins, in from clause order. Each row of the result is a tuple
+/// holding one row position per table, `None` where an outer join padded it with NULLs.
+pub struct JoinedTables<'a> {
+    sources: Vec<Source<'a>>,
+}
+
+impl<'a> JoinedTables<'a> {
+    /// `tables` holds the table of the from clause followed by the table of every join.
+    pub fn new(tables: Vec<&'a Table>, sq: &SelectQuery) -> Result<JoinedTables<'a>, String> {
+        let mut names = vec![sq.from_alias.as_ref().unwrap_or(&sq.from)];
+        for join in &sq.joins {
+            names.push(join.alias.as_ref().unwrap_or(&join.table));
+        }
+
+        let mut sources: Vec<Source> = vec![];
+        for (table, name) in tables.into_iter().zip(names) {
+            if sources.iter().any(|s| s.name.eq_ignore_ascii_case(name)) {
+                return Err(format!(
+                    "table {} appears more than once, give it an alias",
+                    name
+                ));
+            }
+            sources.push(Source {
+                table,
+                name: name.to_string(),
+            });
+        }
+        Ok(JoinedTables { sources })
+    }
+
+    /// Finds the table a column reference belongs to. Returns the table's position and the
+    /// column name without its qualifier.
+    fn resolve(&self, column: &str) -> Result<(usize, String), String> {
+        let found = match column.rsplit_once('.') {
+            Some((qualifier, col_name)) => self
+                .sources
+                .iter()
+                .position(|s| s.name.eq_ignore_ascii_case(qualifier))
+                .filter(|t| self.sources[*t].table.column_exist(col_name.to_string()))
+                .map(|t| (t, col_name.to_string())),
+            None => {
+                let mut owners = (0..self.sources.len())
+                    .filter(|t| self.sources[*t].table.column_exist(column.to_string()));
+                match (owners.next(), owners.next()) {
+                    (Some(_), Some(_)) => {
+                        return Err(format!(
+                            "column {} is ambiguous, qualify it with its table",
+                            column
+                        ))
+                    }
+                    (owner, _) => owner.map(|t| (t, column.to_string())),
+                }
+            }
+        };
+        found.ok_or_else(|| format!("cannot find column {} in the joined tables", column))
+    }
+
+    /// Joins the tables one after the other, returning the tuples in from clause table order.
+    fn join_rows(&self, joins: &[Join]) -> Result<Vec<Vec<Option<usize>>>, String> {
+        let mut tuples = (0..self.sources[0].table.row_count())
+            .map(|row| vec![Some(row)])
+            .collect::<Vec<Vec<Option<usize>>>>();
+
+        for (i, join) in joins.iter().enumerate() {
+            let new = i + 1;
+            let (left, right) = (self.resolve(&join.left)?, self.resolve(&join.right)?);
+            // One side of the condition has to be the joined table, the other one a table
+            // that was joined before it.
+            let (probe, new_col) = match (left.0 == new, right.0 == new) {
+                (false, true) if left.0 < new => (left, right.1),
+                (true, false) if right.0 < new => (right, left.1),
+                _ => {
+                    return Err(format!(
+                        "join condition {} = {} must compare {} with a table before it",
+                        join.left, join.right, self.sources[new].name
+                    ))
+                }
+            };
+            let pairs = self.find_matches(&tuples, probe, new, &new_col);
+            tuples = pad_unmatched(
+                tuples,
+                pairs,
+                join.kind,
+                new,
+                self.sources[new].table.row_count(),
+            );
+        }
+        Ok(tuples)
+    }
+
+    /// Pairs of a tuple's position in `tuples` and a row of table `new` whose `new_col` equals
+    /// the tuple's value in `probe`, ordered by tuple. An index on either key is probed
+    /// directly, otherwise the rows of `new` are hashed on their key first.
+    fn find_matches(
+        &self,
+        tuples: &[Vec<Option<usize>>],
+        (probe_table, probe_col): (usize, String),
+        new: usize,
+        new_col: &str,
+    ) -> Vec<(usize, usize)> {
+        let probe_header = self.sources[probe_table]
+            .table
+            .get_column(probe_col.to_string());
+        let probe_data = self.sources[probe_table]
+            .table
+            .rows
+            .get(&probe_col)
+            .unwrap();
+        let new_header = self.sources[new].table.get_column(new_col.to_string());
+        let new_data = self.sources[new].table.rows.get(new_col).unwrap();
+        // A NULL key, padded or stored, never equals anything.
+        let probe_keys = tuples.iter().enumerate().filter_map(|(i, tuple)| {
+            tuple[probe_table]
+                .filter(|row| !probe_data.is_null(*row))
+                .map(|row| (i, probe_data.get_serialized_value(row)))
+        });
+        let mut pairs = vec![];
+
+        if new_header.is_indexed && new_header.index != ColumnIndex::None {
+            for (i, key) in probe_keys {
+                if let Ok(rows) = new_header.index.get_idx_data(&key) {
+                    pairs.extend(rows.iter().map(|row| (i, *row)));
+                }
+            }
+            return pairs;
+        }
+
+        // While the tuples are still the plain rows of the first table, an index on that
+        // side can be probed with the rows of `new` instead.
+        if new == 1 && probe_header.is_indexed && probe_header.index != ColumnIndex::None {
+            for row in (0..new_data.count()).filter(|row| !new_data.is_null(*row)) {
+                let key = new_data.get_serialized_value(row);
+                if let Ok(probe_rows) = probe_header.index.get_idx_data(&key) {
+                    pairs.extend(probe_rows.iter().map(|probe_row| (*probe_row, row)));
+                }
+            }
+            pairs.sort_unstable();
+            return pairs;
+        }
+
+        let mut rows_by_key: HashMap<String, Vec<usize>> = HashMap::new();
+        for row in (0..new_data.count()).filter(|row| !new_data.is_null(*row)) {
+            rows_by_key
+                .entry(new_data.get_serialized_value(row))
+                .or_default()
+                .push(row);
+        }
+        for (i, key) in probe_keys {
+            if let Some(rows) = rows_by_key.get(&key) {
+                pairs.extend(rows.iter().map(|row| (i, *row)));
+            }
+        }
+        pairs
+    }
+
+    /// Tells for every tuple whether it satisfies `predicate`.
+    fn evaluate_predicate(
+        &self,
+        predicate: &Predicate,
+        tuples: &[Vec<Option<usize>>],
+    ) -> Result<Vec<bool>, String> {
+        match predicate {
+            Predicate::And(left, right) => Ok(self
+                .evaluate_predicate(left, tuples)?
+                .into_iter()
+                .zip(self.evaluate_predicate(right, tuples)?)
+                .map(|(l, r)| l && r)
+                .collect()),
+            Predicate::Or(left, right) => Ok(self
+                .evaluate_predicate(left, tuples)?
+                .into_iter()
+                .zip(self.evaluate_predicate(right, tuples)?)
+                .map(|(l, r)| l || r)
+                .collect()),
+            Predicate::Not(inner) => self.evaluate_predicate(&inner.negate(), tuples),
+            Predicate::IsNull(column) | Predicate::IsNotNull(column) => {
+                // Padded rows are NULL in every column.
+                let (t, col_name) = self.resolve(column)?;
+                let data = self.sources[t].table.rows.get(&col_name).unwrap();
+                let wants_null = matches!(predicate, Predicate::IsNull(_));
+                Ok(tuples
+                    .iter()
+                    .map(|tuple| tuple[t].is_none_or(|row| data.is_null(row)) == wants_null)
+                    .collect())
+            }
+            Predicate::Expression(expr) => {
+                if expr.aggregate.is_some() {
+                    return Err(format!(
+                        "{} is an aggregate, aggregates can only be filtered on in HAVING",
+                        expr.left
+                    ));
+                }
+                // Let the table evaluate the comparison on the rows the tuples point at, a
+                // padded row compares to nothing.
+                let (t, col_name) = self.resolve(&expr.left)?;
+                let mut rows = tuples
+                    .iter()
+                    .filter_map(|tuple| tuple[t])
+                    .collect::<Vec<usize>>();
+                rows.sort_unstable();
+                rows.dedup();
+                let table_expr = Expression {
+                    left: col_name,
+                    ..expr.clone()
+                };
+                let matched = self.sources[t]
+                    .table
+                    .evaluate_expression(&table_expr, Some(&rows))?;
+                Ok(tuples
+                    .iter()
+                    .map(|tuple| tuple[t].is_some_and(|row| matched.binary_search(&row).is_ok()))
+                    .collect())
+            }
+        }
+    }
+
+    fn sort_rows(
+        &self,
+        tuples: &mut [Vec<Option<usize>>],
+        order_by: &[OrderBy],
+    ) -> Result<(), String> {
+        let mut keys = vec![];
+        for key in order_by {
+            let (t, col_name) = self.resolve(&key.column)?;
+            keys.push((t, self.sources[t].table.rows.get(&col_name).unwrap(), key));
+        }
+        tuples.sort_by(|a, b| {
+            for (t, column, key) in &keys {
+                let ordering = match (a[*t], b[*t]) {
+                    (Some(a), Some(b)) => column.compare_rows_by_key(a, b, key),
+                    (None, None) => Ordering::Equal,
+                    // Padded rows are NULL in every column.
+                    (None, Some(_)) if key.nulls_first => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some(_), None) if key.nulls_first => Ordering::Greater,
+                    (Some(_), None) => Ordering::Less,
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+        Ok(())
+    }
+
+    /// Runs `sq` and returns its result along with the type of each column.
+    pub fn select_result(&self, sq: &SelectQuery) -> Result<QueryResult, String> {
+        if !sq.group_by.is_empty() || sq.having.is_some() {
+            return Err("GROUP BY is not supported on joined tables yet".to_string());
+        }
+
+        let mut columns = vec![];
+        for p in &sq.projection {
+            match p {
+                Projection::Column(column) if column == "*" => {
+                    for (t, source) in self.sources.iter().enumerate() {
+                        for col in &source.table.columns {
+                            let label = format!("{}.{}", source.name, col.name);
+                            columns.push((label, t, col.name.to_string()));
+                        }
+                    }
+                }
+                Projection::Column(column) => {
+                    let (t, col_name) = self.resolve(column)?;
+                    columns.push((column.to_string(), t, col_name));
+                }
+                Projection::Aggregate(aggregate) => {
+                    return Err(format!(
+                        "{} cannot be computed on joined tables yet",
+                        aggregate
+                    ))
+                }
+            }
+        }
+
+        let mut tuples = self.join_rows(&sq.joins)?;
+        if let Some(predicate) = &sq.where_clause {
+            let matched = self.evaluate_predicate(predicate, &tuples)?;
+            tuples = tuples
+                .into_iter()
+                .zip(matched)
+                .filter(|(_, matched)| *matched)
+                .map(|(tuple, _)| tuple)
+                .collect();
+        }
+        self.sort_rows(&mut tuples, &sq.order_by)?;
+
+        let tuples = tuples
+            .into_iter()
+            .skip(sq.offset)
+            .take(sq.limit.unwrap_or(usize::MAX))
+            .collect::<Vec<Vec<Option<usize>>>>();
+        let data = columns
+            .iter()
+            .map(|(_, t, col_name)| {
+                let column = self.sources[*t].table.rows.get(col_name).unwrap();
+                tuples
+                    .iter()
+                    .map(|tuple| tuple[*t].and_then(|row| column.get_value(row)))
+                    .collect()
+            })
+            .collect();
+        let columns = columns
+            .into_iter()
+            .map(|(label, t, col_name)| {
+                let table = self.sources[t].table;
+                (label, table.get_column(col_name).datatype.clone())
+            })
+            .collect();
+        Ok(QueryResult { columns, data })
+    }
+}
+
+/// Turns the matches of one join into the new tuples. Tuples without a match are kept with
+/// a NULL row for LEFT and FULL joins, rows of the joined table that matched nothing are kept
+/// with NULLs for every earlier table for RIGHT and FULL joins.
+fn pad_unmatched(
+    tuples: Vec<Vec<Option<usize>>>,
+    pairs: Vec<(usize, usize)>,
+    kind: JoinKind,
+    new: usize,
+    new_row_count: usize,
+) -> Vec<Vec<Option<usize>>> {
+    let keeps_left = kind == JoinKind::Left || kind == JoinKind::Full;
+    let keeps_right = kind == JoinKind::Right || kind == JoinKind::Full;
+    let mut is_matched = vec![false; new_row_count];
+    let mut pairs = pairs.into_iter().peekable();
+    let mut joined = vec![];
+
+    for (i, tuple) in tuples.into_iter().enumerate() {
+        let mut has_match = false;
+        while let Some((_, row)) = pairs.next_if(|(tuple_idx, _)| *tuple_idx == i) {
+            let mut joined_tuple = tuple.clone();
+            joined_tuple.push(Some(row));
+            joined.push(joined_tuple);
+            is_matched[row] = true;
+            has_match = true;
+        }
+        if !has_match && keeps_left {
+            let mut joined_tuple = tuple;
+            joined_tuple.push(None);
+            joined.push(joined_tuple);
+        }
+    }
+
+    if keeps_right {
+        for row in (0..new_row_count).filter(|row| !is_matched[*row]) {
+            let mut joined_tuple = vec![None; new];
+            joined_tuple.push(Some(row));
+            joined.push(joined_tuple);
+        }
+    }
+    joined
+}
@@ -0,0 +1,54 @@
+//! Dates and times are stored as integers: a DATE as days since 1970-01-01, a TIME as seconds
+//! since midnight and a DATETIME or TIMESTAMP as seconds since 1970-01-01 00:00:00.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+use std::convert::TryFrom;
+
+fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
+pub fn parse_date(val: &str) -> Result<i32, String> {
+    NaiveDate::parse_from_str(val, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| i32::try_from((date - epoch()).num_days()).ok())
+        .ok_or_else(|| format!("{} is not a valid date", val))
+}
+
+pub fn format_date(days: i32) -> String {
+    (epoch() + TimeDelta::days(days as i64))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+pub fn parse_time(val: &str) -> Result<i32, String> {
+    NaiveTime::parse_from_str(val, "%H:%M:%S%.f")
+        .or_else(|_| NaiveTime::parse_from_str(val, "%H:%M"))
+        .map(|time| time.num_seconds_from_midnight() as i32)
+        .map_err(|_| format!("{} is not a valid time", val))
+}
+
+pub fn format_time(seconds: i32) -> String {
+    NaiveTime::from_num_seconds_from_midnight_opt(seconds as u32, 0)
+        .unwrap()
+        .format("%H:%M:%S")
+        .to_string()
+}
+
+/// Parses `YYYY-MM-DD HH:MM:SS`, with a `T` in place of the space as well. A bare date stands
+/// for its midnight, so `order_date > '2006-01-01'` works on a DATETIME column.
+pub fn parse_datetime(val: &str) -> Result<i64, String> {
+    NaiveDateTime::parse_from_str(val, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(val, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| NaiveDate::parse_from_str(val, "%Y-%m-%d").map(|d| d.and_time(NaiveTime::MIN)))
+        .map(|datetime| datetime.and_utc().timestamp())
+        .map_err(|_| format!("{} is not a valid datetime", val))
+}
+
+pub fn format_datetime(seconds: i64) -> String {
+    DateTime::from_timestamp(seconds, 0)
+        .unwrap()
+        .naive_utc()
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}